@@ -1,33 +1,12 @@
-use std::collections::hash_map::Entry;
-use std::collections::HashMap;
 use std::fs;
 use std::time::Instant;
 
-use fancy_regex::Regex;
-use lazy_static::lazy_static;
+use aoc2015::utils::bespoke::{BitWidth, LogicCircuit};
 
 const PROBLEM_NAME: &str = "Some Assembly Required";
 const PROBLEM_INPUT_FILE: &str = "./input/day07.txt";
 const PROBLEM_DAY: u64 = 7;
 
-lazy_static! {
-    static ref REGEX_VALUE: Regex = Regex::new(r"^([a-z]+|\d+) -> ([a-z]+)$").unwrap();
-    static ref REGEX_UNARY: Regex = Regex::new(r"^NOT ([a-z]+|\d+) -> ([a-z]+)$").unwrap();
-    static ref REGEX_BINARY: Regex =
-        Regex::new(r"^([a-z]+|\d+) (AND|LSHIFT|RSHIFT|OR) ([a-z]+|\d+) -> ([a-z]+)$").unwrap();
-}
-
-/// Represents the different operations observed in the problem.
-#[derive(Clone, PartialEq, Eq)]
-enum Operation {
-    Value { left: String },
-    And { left: String, right: String },
-    LShift { left: String, right: String },
-    RShift { left: String, right: String },
-    Not { left: String },
-    Or { left: String, right: String },
-}
-
 /// Processes the AOC 2015 Day 07 input file and solves both parts of the problem. Solutions are
 /// printed to stdout.
 pub fn main() {
@@ -62,142 +41,39 @@ pub fn main() {
 }
 
 /// Processes the AOC 2015 Day 07 input file into the format required by the solver functions.
-/// Returned value is hashmap mapping each wire to the operation providing the value feeding into
-/// the wire.
-fn process_input_file(filename: &str) -> HashMap<String, Operation> {
+/// Returned value is logic circuit containing the gates providing the value feeding into each wire,
+/// with 16-bit signals.
+fn process_input_file(filename: &str) -> LogicCircuit {
     // Read contents of problem input file
     let raw_input = fs::read_to_string(filename).unwrap();
     // Process input file contents into data structure
-    let mut wire_ops: HashMap<String, Operation> = HashMap::new();
-    for line in raw_input.lines() {
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
-        }
-        // Extract the wire and operation from the current line
-        if let Ok(Some(caps)) = REGEX_VALUE.captures(line) {
-            let left = caps[1].to_string();
-            let wire = caps[2].to_string();
-            wire_ops.insert(wire, Operation::Value { left });
-        } else if let Ok(Some(caps)) = REGEX_UNARY.captures(line) {
-            let left = caps[1].to_string();
-            let wire = caps[2].to_string();
-            wire_ops.insert(wire, Operation::Not { left });
-        } else if let Ok(Some(caps)) = REGEX_BINARY.captures(line) {
-            let left = caps[1].to_string();
-            let op_type = &caps[2];
-            let right = caps[3].to_string();
-            let wire = caps[4].to_string();
-            let op = match op_type {
-                "AND" => Operation::And { left, right },
-                "OR" => Operation::Or { left, right },
-                "LSHIFT" => Operation::LShift { left, right },
-                "RSHIFT" => Operation::RShift { left, right },
-                _ => panic!("Bad binary operation type: {}", op_type),
-            };
-            wire_ops.insert(wire, op);
-        } else {
-            panic!("Day 7: bad format input line // {}", line);
-        }
+    if let Some(circuit) = LogicCircuit::new(&raw_input, BitWidth::Sixteen) {
+        return circuit;
     }
-    wire_ops
+    panic!("Day 7: bad format input file");
 }
 
 /// Solves AOC 2015 Day 07 Part 1 // Determines the value that is provided to wire "a".
-fn solve_part1(wire_ops: &HashMap<String, Operation>) -> u16 {
-    determine_target_wire_value(&String::from("a"), wire_ops)
+fn solve_part1(circuit: &LogicCircuit) -> u64 {
+    circuit.evaluate_wire("a").unwrap()
 }
 
 /// Solves AOC 2015 Day 07 Part 2 // Determines the value that is provided to wire "a" after
 /// mapping the initial value of wire "a" to wire "b" and recalculating the wire "a" value.
-fn solve_part2(wire_ops: &HashMap<String, Operation>) -> u16 {
+fn solve_part2(circuit: &LogicCircuit) -> u64 {
     // Calculate initial value of wire "a"
-    let wire_a_value = determine_target_wire_value(&String::from("a"), wire_ops);
+    let wire_a_value = circuit.evaluate_wire("a").unwrap();
     // Update the value provided to wire "b"
-    let mut new_wires = wire_ops.clone();
-    new_wires.insert(
-        String::from("b"),
-        Operation::Value {
-            left: wire_a_value.to_string(),
-        },
-    );
+    let mut new_circuit = circuit.clone();
+    new_circuit.set_wire_value("b", wire_a_value);
     // Recalculate value of wire "a"
-    determine_target_wire_value(&String::from("a"), &new_wires)
-}
-
-/// Determines the value provided to the target wire.
-fn determine_target_wire_value(target_wire: &String, wire_ops: &HashMap<String, Operation>) -> u16 {
-    let mut wire_values: HashMap<String, u16> = HashMap::new();
-    determine_target_wire_value_recursive(target_wire, wire_ops, &mut wire_values)
-}
-
-/// Recursive support function used to determine the value provided to the target wire.
-fn determine_target_wire_value_recursive(
-    target_wire: &String,
-    wire_ops: &HashMap<String, Operation>,
-    wire_values: &mut HashMap<String, u16>,
-) -> u16 {
-    // Check if the wire value has already been found
-    if let Entry::Occupied(e) = wire_values.entry(target_wire.to_string()) {
-        return *e.get();
-    }
-    // Calculate the value fed to the target wire
-    let wire_value = evaluate_wire_value(wire_ops, target_wire, wire_values);
-    // Records the value fed to the target wire
-    wire_values.insert(target_wire.to_string(), wire_value);
-    wire_value
-}
-
-/// Evaluates the value of the given wire.
-fn evaluate_wire_value(
-    wire_ops: &HashMap<String, Operation>,
-    wire: &String,
-    wire_values: &mut HashMap<String, u16>,
-) -> u16 {
-    match wire_ops.get(wire).unwrap() {
-        Operation::Value { left } => get_term_value(left, wire_ops, wire_values),
-        Operation::And { left, right } => {
-            let left = get_term_value(left, wire_ops, wire_values);
-            let right = get_term_value(right, wire_ops, wire_values);
-            left & right
-        }
-        Operation::LShift { left, right } => {
-            let left = get_term_value(left, wire_ops, wire_values);
-            let right = get_term_value(right, wire_ops, wire_values);
-            left << right
-        }
-        Operation::RShift { left, right } => {
-            let left = get_term_value(left, wire_ops, wire_values);
-            let right = get_term_value(right, wire_ops, wire_values);
-            left >> right
-        }
-        Operation::Not { left } => {
-            let left = get_term_value(left, wire_ops, wire_values);
-            !left
-        }
-        Operation::Or { left, right } => {
-            let left = get_term_value(left, wire_ops, wire_values);
-            let right = get_term_value(right, wire_ops, wire_values);
-            left | right
-        }
-    }
-}
-
-/// Gets the value of the given term, if it is a specific value or the name of a wire.
-fn get_term_value(
-    term: &String,
-    wires: &HashMap<String, Operation>,
-    wire_values: &mut HashMap<String, u16>,
-) -> u16 {
-    if let Ok(value) = term.parse::<u16>() {
-        value
-    } else {
-        determine_target_wire_value_recursive(term, wires, wire_values)
-    }
+    new_circuit.evaluate_wire("a").unwrap()
 }
 
 #[cfg(test)]
 mod test {
+    use std::collections::HashMap;
+
     use super::*;
 
     /// Tests the Day 07 Part 1 solver method against the actual problem solution.
@@ -215,4 +91,99 @@ mod test {
         let solution = solve_part2(&input);
         assert_eq!(40149, solution);
     }
+
+    /// Tests the additional gate types against a small 8-bit circuit.
+    #[test]
+    fn test_day07_extra_gates_8bit() {
+        let circuit = LogicCircuit::new(
+            "240 -> x\n\
+             170 -> y\n\
+             x XOR y -> d\n\
+             x NAND y -> e\n\
+             x NOR y -> f\n\
+             MUX 15 x y -> g\n\
+             x LSHIFT 4 -> h\n\
+             NOT 511 -> i",
+            BitWidth::from_bits(8).unwrap(),
+        )
+        .unwrap();
+        let expected = [
+            ("d", 90),
+            ("e", 95),
+            ("f", 5),
+            ("g", 160),
+            ("h", 0),
+            ("i", 0),
+        ];
+        for (wire, value) in expected {
+            assert_eq!(Some(value), circuit.evaluate_wire(wire));
+        }
+    }
+
+    /// Tests that the simplified actual circuit, with wire "b" left as a free input, still gives
+    /// the Part 2 solution once wire "b" is overridden.
+    #[test]
+    fn test_day07_simplify_actual() {
+        let input = process_input_file(PROBLEM_INPUT_FILE);
+        let (simplified, gates_eliminated) = input.simplify(&["a"], &["b"]);
+        assert_eq!(
+            input.gate_count() - simplified.gate_count(),
+            gates_eliminated
+        );
+        assert!(gates_eliminated > 0);
+        // Emitted circuit should be parsed back into an equivalent circuit
+        let mut reparsed = LogicCircuit::new(&simplified.to_string(), BitWidth::Sixteen).unwrap();
+        assert_eq!(simplified.to_string(), reparsed.to_string());
+        reparsed.set_wire_value("b", 956);
+        assert_eq!(Some(40149), reparsed.evaluate_wire("a"));
+    }
+
+    /// Tests that the simplification pass removes identity gates, merges duplicate gates and drops
+    /// dead wires.
+    #[test]
+    fn test_day07_simplify_identities() {
+        let circuit = LogicCircuit::new(
+            "x OR 0 -> p\n\
+             65535 AND y -> q\n\
+             p AND q -> r\n\
+             q AND p -> s\n\
+             r OR s -> t\n\
+             123 LSHIFT 2 -> u\n\
+             t OR u -> z",
+            BitWidth::Sixteen,
+        )
+        .unwrap();
+        let (simplified, gates_eliminated) = circuit.simplify(&["z"], &[]);
+        assert_eq!(5, gates_eliminated);
+        assert_eq!("x AND y -> r\nr OR 492 -> z\n", simplified.to_string());
+    }
+
+    /// Tests that the symbolic expression for wire "a" in the actual circuit, with wire "b" left
+    /// as a free input, gives the Part 1 and Part 2 solutions for the original and overridden
+    /// values of wire "b".
+    #[test]
+    fn test_day07_expand_wire_actual() {
+        let input = process_input_file(PROBLEM_INPUT_FILE);
+        let expression = input.expand_wire("a", &["b"]);
+        let width = input.width();
+        let inputs = HashMap::from([(String::from("b"), 14146)]);
+        assert_eq!(Some(956), expression.evaluate(&inputs, width));
+        let inputs = HashMap::from([(String::from("b"), 956)]);
+        assert_eq!(Some(40149), expression.evaluate(&inputs, width));
+    }
+
+    /// Tests that sub-expressions used more than once are shown as let-bindings.
+    #[test]
+    fn test_day07_expand_wire_shared() {
+        let circuit = LogicCircuit::new(
+            "x AND y -> p\n\
+             p LSHIFT 1 -> q\n\
+             p OR q -> r\n\
+             NOT r -> z",
+            BitWidth::Sixteen,
+        )
+        .unwrap();
+        let expression = circuit.expand_wire("z", &[]);
+        assert_eq!("let p = x & y\nin ~(p | (p << 1))", expression.to_string());
+    }
 }
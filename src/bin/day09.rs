@@ -1,5 +1,6 @@
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
+use std::env;
 use std::fs;
 use std::time::Instant;
 
@@ -10,9 +11,134 @@ const PROBLEM_NAME: &str = "All in a Single Night";
 const PROBLEM_INPUT_FILE: &str = "./input/day09.txt";
 const PROBLEM_DAY: u64 = 9;
 
+/// Largest number of cities for which the Held-Karp solver is used (memory use grows with the
+/// number of cities times two to the power of the number of cities).
+const MAX_EXACT_CITIES: usize = 20;
+/// Held-Karp table entry for a partial route that cannot be made using the roads in the graph.
+const UNREACHABLE: u64 = u64::MAX;
+/// Cost used by the heuristic route optimiser for travelling between cities with no road.
+const MISSING_ROAD_PENALTY: i64 = 1 << 40;
+/// Number of nearest cities chosen between at random when building routes after the first restart.
+const NEIGHBOUR_CHOICES: usize = 3;
+/// Longest run of cities moved at once by the Or-opt improvement.
+const OR_OPT_MAX_SEGMENT: usize = 3;
+const DEFAULT_RESTARTS: usize = 20;
+const DEFAULT_SEED: u64 = 0x2015_0009;
+
+/// Type definition for a route visiting every city exactly once, given as the total distance
+/// travelled and the names of the cities in the order visited.
+type Route = (u64, Vec<String>);
+
+/// Represents the objective used when choosing between routes visiting every city.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Objective {
+    Shortest,
+    Longest,
+}
+
+impl Objective {
+    /// Checks if the candidate distance is better than the current distance under the objective.
+    fn improves(&self, candidate: u64, current: u64) -> bool {
+        match self {
+            Objective::Shortest => candidate < current,
+            Objective::Longest => candidate > current,
+        }
+    }
+
+    /// Converts the distance of a road into the cost minimised by the heuristic route optimiser.
+    /// Missing roads have a cost greater than any route using only roads in the graph.
+    fn cost(&self, distance: Option<u64>) -> i64 {
+        match (self, distance) {
+            (_, None) => MISSING_ROAD_PENALTY,
+            (Objective::Shortest, Some(d)) => d as i64,
+            (Objective::Longest, Some(d)) => -(d as i64),
+        }
+    }
+}
+
+/// Represents a route found by the heuristic route optimiser, along with the distance of the exact
+/// best route if the graph is small enough for it to be computed.
+struct HeuristicRoute {
+    route: Route,
+    exact: Option<u64>,
+}
+
+impl HeuristicRoute {
+    /// Gets how far the distance of the route is from the exact best distance (0 if the route is
+    /// optimal). Returned value is None if the exact best distance was not computed.
+    fn gap(&self) -> Option<u64> {
+        self.exact.map(|exact| self.route.0.abs_diff(exact))
+    }
+
+    /// Gets the gap as a percentage of the exact best distance. Returned value is None if the exact
+    /// best distance was not computed.
+    fn gap_percent(&self) -> Option<f64> {
+        match (self.gap(), self.exact) {
+            (Some(_), Some(0)) => Some(0.0),
+            (Some(gap), Some(exact)) => Some(gap as f64 / exact as f64 * 100.0),
+            _ => None,
+        }
+    }
+}
+
+/// Xorshift pseudo-random number generator used to seed the restarts of the heuristic route
+/// optimiser, so the routes found are repeatable for the same seed.
+struct Xorshift {
+    state: u64,
+}
+
+impl Xorshift {
+    fn new(seed: u64) -> Xorshift {
+        // State of zero would only ever generate zero
+        Xorshift { state: seed.max(1) }
+    }
+
+    /// Generates a pseudo-random index less than the given length (which must be non-zero).
+    fn next_index(&mut self, len: usize) -> usize {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        (self.state % len as u64) as usize
+    }
+}
+
 /// Processes the AOC 2015 Day 09 input file and solves both parts of the problem. Solutions are
 /// printed to stdout.
+///
+/// Giving "route" as the argument instead prints the cities visited (in order) along the shortest
+/// and longest routes. Giving "heuristic [restarts] [seed]" prints the routes found by the
+/// heuristic route optimiser along with their gaps against the exact routes (restarts must be at
+/// least 1). Giving "brute" as the argument solves both parts of the problem using the brute force
+/// search over every order of the cities instead of the Held-Karp solver.
 pub fn main() {
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(|arg| arg.as_str()) {
+        Some("route") => {
+            let input = process_input_file(PROBLEM_INPUT_FILE);
+            return print_routes(&input);
+        }
+        Some("heuristic") => {
+            let restarts = args.get(2).map_or(DEFAULT_RESTARTS, |arg| {
+                arg.parse::<usize>()
+                    .ok()
+                    .filter(|&restarts| restarts > 0)
+                    .expect("Invalid restart count (must be at least 1)!")
+            });
+            let seed = args.get(3).map_or(DEFAULT_SEED, |arg| {
+                arg.parse::<u64>().expect("Invalid seed!")
+            });
+            let input = process_input_file(PROBLEM_INPUT_FILE);
+            return print_heuristic_routes(&input, restarts, seed);
+        }
+        Some("brute") => {
+            let input = process_input_file(PROBLEM_INPUT_FILE);
+            let (min_dist, max_dist) = find_min_max_distances_to_visit_all_nodes(&input);
+            println!("[+] Part 1 (brute force): {}", min_dist.unwrap());
+            println!("[+] Part 2 (brute force): {}", max_dist.unwrap());
+            return;
+        }
+        _ => (),
+    }
     let start = Instant::now();
     // Input processing
     let input = process_input_file(PROBLEM_INPUT_FILE);
@@ -92,7 +218,7 @@ fn add_edges_between_nodes(
 /// Solves AOC 2015 Day 09 Part 1 // Determines the minimum distance required to visit all nodes in
 /// the graph.
 fn solve_part1(edges: &HashMap<String, HashMap<String, u64>>) -> u64 {
-    if let (Some(min_dist), _) = find_min_max_distances_to_visit_all_nodes(edges) {
+    if let Some((min_dist, _)) = find_route_held_karp(edges, Objective::Shortest) {
         return min_dist;
     }
     panic!("Did not find the minimum distance path!");
@@ -101,14 +227,323 @@ fn solve_part1(edges: &HashMap<String, HashMap<String, u64>>) -> u64 {
 /// Solves AOC 2015 Day 09 Part 2 // Determines the maximum distance required to visit all nodes in
 /// the graph.
 fn solve_part2(edges: &HashMap<String, HashMap<String, u64>>) -> u64 {
-    if let (_, Some(max_dist)) = find_min_max_distances_to_visit_all_nodes(edges) {
+    if let Some((max_dist, _)) = find_route_held_karp(edges, Objective::Longest) {
         return max_dist;
     }
     panic!("Did not find the maximum distance path!");
 }
 
-/// Finds the minimum and maximum distances needed to visit all nodes in the graph. Returned value
-/// is a tuple containing the minimum and maximum distances found (in that order).
+/// Prints the cities visited along the shortest and longest routes, along with their distances.
+fn print_routes(edges: &HashMap<String, HashMap<String, u64>>) {
+    for objective in [Objective::Shortest, Objective::Longest] {
+        match find_route_held_karp(edges, objective) {
+            Some((dist, cities)) => {
+                println!("[+] {:?}: {} = {}", objective, cities.join(" -> "), dist)
+            }
+            None => println!("[!] {:?}: no route visits every city", objective),
+        }
+    }
+}
+
+/// Prints the cities visited along the shortest and longest routes found by the heuristic route
+/// optimiser, along with their distances and gaps against the exact routes.
+fn print_heuristic_routes(
+    edges: &HashMap<String, HashMap<String, u64>>,
+    restarts: usize,
+    seed: u64,
+) {
+    for objective in [Objective::Shortest, Objective::Longest] {
+        match find_route_heuristic(edges, objective, restarts, seed) {
+            Some(heuristic) => {
+                let (dist, cities) = &heuristic.route;
+                println!("[+] {:?}: {} = {}", objective, cities.join(" -> "), dist);
+                match (heuristic.exact, heuristic.gap(), heuristic.gap_percent()) {
+                    (Some(exact), Some(gap), Some(percent)) => {
+                        println!("    Exact: {} (gap {}, {:.2}%)", exact, gap, percent)
+                    }
+                    _ => println!(
+                        "    Exact: not computed (more than {} cities)",
+                        MAX_EXACT_CITIES
+                    ),
+                }
+            }
+            None => println!("[!] {:?}: no route visits every city", objective),
+        }
+    }
+}
+
+/// Gets the names of the cities in the graph (in sorted order) and the matrix of distances between
+/// them, indexed by the position of each city in the names. Distances are None where there is no
+/// road between the two cities.
+fn distance_matrix(
+    edges: &HashMap<String, HashMap<String, u64>>,
+) -> (Vec<String>, Vec<Vec<Option<u64>>>) {
+    let cities: Vec<String> = edges.keys().cloned().sorted().collect();
+    let matrix = cities
+        .iter()
+        .map(|from| {
+            cities
+                .iter()
+                .map(|to| edges.get(from).unwrap().get(to).copied())
+                .collect()
+        })
+        .collect();
+    (cities, matrix)
+}
+
+/// Finds the route visiting every city exactly once that best meets the objective, using the
+/// Held-Karp dynamic programming algorithm over the subsets of cities visited and the city last
+/// visited. Runs in O(2^n * n^2) time for n cities, so is only suitable for graphs with up to
+/// about 20 cities (panics if there are more than MAX_EXACT_CITIES). Returned value is None if no
+/// route visits every city. Ties are broken in favour of the route ending at the city first in
+/// sorted order.
+fn find_route_held_karp(
+    edges: &HashMap<String, HashMap<String, u64>>,
+    objective: Objective,
+) -> Option<Route> {
+    let (cities, dist) = distance_matrix(edges);
+    let n = cities.len();
+    if n > MAX_EXACT_CITIES {
+        panic!("Too many cities for the Held-Karp solver! // {n}");
+    }
+    if n == 0 {
+        return None;
+    }
+    // Best distance of route visiting the cities in each subset (bitmask) and ending at each city
+    let mut table: Vec<u64> = vec![UNREACHABLE; (1 << n) * n];
+    for city in 0..n {
+        table[(1 << city) * n + city] = 0;
+    }
+    for visited in 1..(1usize << n) {
+        for last in (0..n).filter(|&last| visited & (1 << last) != 0) {
+            let current = table[visited * n + last];
+            if current == UNREACHABLE {
+                continue;
+            }
+            for next in (0..n).filter(|&next| visited & (1 << next) == 0) {
+                if let Some(d) = dist[last][next] {
+                    let entry = &mut table[(visited | (1 << next)) * n + next];
+                    if *entry == UNREACHABLE || objective.improves(current + d, *entry) {
+                        *entry = current + d;
+                    }
+                }
+            }
+        }
+    }
+    // Find the best route visiting all cities, then retrace it back to the starting city
+    let mut visited = (1 << n) - 1;
+    let mut last = (0..n)
+        .filter(|&last| table[visited * n + last] != UNREACHABLE)
+        .reduce(|best, last| {
+            match objective.improves(table[visited * n + last], table[visited * n + best]) {
+                true => last,
+                false => best,
+            }
+        })?;
+    let total = table[visited * n + last];
+    let mut order = vec![last];
+    while visited.count_ones() > 1 {
+        let current = table[visited * n + last];
+        let previous_visited = visited ^ (1 << last);
+        let previous = (0..n)
+            .find(|&prev| {
+                let entry = table[previous_visited * n + prev];
+                entry != UNREACHABLE && dist[prev][last].is_some_and(|d| entry + d == current)
+            })
+            .unwrap();
+        order.push(previous);
+        visited = previous_visited;
+        last = previous;
+    }
+    order.reverse();
+    Some((
+        total,
+        order.into_iter().map(|i| cities[i].clone()).collect(),
+    ))
+}
+
+/// Finds a good route visiting every city exactly once for the objective, for graphs too large for
+/// the Held-Karp solver. Each restart builds a route by repeatedly travelling to the nearest city
+/// not yet visited (picking at random between the few nearest cities after the first restart),
+/// then improves it with 2-opt and Or-opt moves until neither gives an improvement. Roads are
+/// assumed to have the same distance in both directions. Starting cities and choices are drawn from
+/// a pseudo-random number generator with the given seed. The exact best distance is also computed
+/// for graphs with up to MAX_EXACT_CITIES cities, so the gap can be reported. Returned value is
+/// None if the restart count is 0 or no route using only roads in the graph was found.
+fn find_route_heuristic(
+    edges: &HashMap<String, HashMap<String, u64>>,
+    objective: Objective,
+    restarts: usize,
+    seed: u64,
+) -> Option<HeuristicRoute> {
+    let (cities, dist) = distance_matrix(edges);
+    let n = cities.len();
+    if n == 0 || restarts == 0 {
+        return None;
+    }
+    let costs: Vec<Vec<i64>> = dist
+        .iter()
+        .map(|row| row.iter().map(|&d| objective.cost(d)).collect())
+        .collect();
+    let mut rng = Xorshift::new(seed);
+    let mut best: Option<(i64, Vec<usize>)> = None;
+    for restart in 0..restarts {
+        let choices = match restart {
+            0 => 1,
+            _ => NEIGHBOUR_CHOICES,
+        };
+        let start = rng.next_index(n);
+        let mut path = build_nearest_neighbour_route(&costs, start, choices, &mut rng);
+        improve_route(&costs, &mut path);
+        let cost = route_cost(&costs, &path);
+        if best.as_ref().is_none_or(|(best_cost, _)| cost < *best_cost) {
+            best = Some((cost, path));
+        }
+    }
+    let (_, path) = best?;
+    let mut total = 0;
+    for pair in path.windows(2) {
+        total += dist[pair[0]][pair[1]]?;
+    }
+    let exact = match n <= MAX_EXACT_CITIES {
+        true => find_route_held_karp(edges, objective).map(|(exact, _)| exact),
+        false => None,
+    };
+    Some(HeuristicRoute {
+        route: (total, path.into_iter().map(|i| cities[i].clone()).collect()),
+        exact,
+    })
+}
+
+/// Calculates the total cost of travelling along the route.
+fn route_cost(costs: &[Vec<i64>], path: &[usize]) -> i64 {
+    path.windows(2).map(|pair| costs[pair[0]][pair[1]]).sum()
+}
+
+/// Builds a route from the starting city by repeatedly travelling to one of the given number of
+/// cheapest cities not yet visited (chosen at random, so always the cheapest if the number is 1).
+fn build_nearest_neighbour_route(
+    costs: &[Vec<i64>],
+    start: usize,
+    choices: usize,
+    rng: &mut Xorshift,
+) -> Vec<usize> {
+    let n = costs.len();
+    let mut visited = vec![false; n];
+    visited[start] = true;
+    let mut path = vec![start];
+    while path.len() < n {
+        let last = *path.last().unwrap();
+        let candidates: Vec<usize> = (0..n)
+            .filter(|&city| !visited[city])
+            .sorted_by_key(|&city| costs[last][city])
+            .collect();
+        let next = candidates[rng.next_index(choices.min(candidates.len()))];
+        visited[next] = true;
+        path.push(next);
+    }
+    path
+}
+
+/// Improves the route with 2-opt and Or-opt moves until neither gives a cheaper route.
+fn improve_route(costs: &[Vec<i64>], path: &mut Vec<usize>) {
+    loop {
+        let two_opt_improved = apply_two_opt(costs, path);
+        let or_opt_improved = apply_or_opt(costs, path);
+        if !two_opt_improved && !or_opt_improved {
+            return;
+        }
+    }
+}
+
+/// Applies each 2-opt move (reversing the run of cities between two positions in the route) that
+/// makes the route cheaper. Returns true if any move was applied.
+fn apply_two_opt(costs: &[Vec<i64>], path: &mut [usize]) -> bool {
+    let n = path.len();
+    let mut improved = false;
+    for i in 0..n {
+        for j in i + 1..n {
+            // Only the roads into and out of the reversed run change
+            let mut change = 0;
+            if i > 0 {
+                change += costs[path[i - 1]][path[j]] - costs[path[i - 1]][path[i]];
+            }
+            if j < n - 1 {
+                change += costs[path[i]][path[j + 1]] - costs[path[j]][path[j + 1]];
+            }
+            if change < 0 {
+                path[i..=j].reverse();
+                improved = true;
+            }
+        }
+    }
+    improved
+}
+
+/// Applies the first Or-opt move found (moving a short run of cities elsewhere in the route, in
+/// either direction) that makes the route cheaper. Returns true if a move was applied.
+fn apply_or_opt(costs: &[Vec<i64>], path: &mut Vec<usize>) -> bool {
+    let n = path.len();
+    for len in 1..=OR_OPT_MAX_SEGMENT.min(n.saturating_sub(1)) {
+        for i in 0..=n - len {
+            let j = i + len - 1;
+            // Change in cost from taking the run out of the route
+            let mut removal = 0;
+            if i > 0 {
+                removal -= costs[path[i - 1]][path[i]];
+            }
+            if j < n - 1 {
+                removal -= costs[path[j]][path[j + 1]];
+            }
+            if i > 0 && j < n - 1 {
+                removal += costs[path[i - 1]][path[j + 1]];
+            }
+            let segment: Vec<usize> = path[i..=j].to_vec();
+            let rest: Vec<usize> = path[..i]
+                .iter()
+                .chain(path[j + 1..].iter())
+                .copied()
+                .collect();
+            for position in (0..=rest.len()).filter(|&position| position != i) {
+                for reversed in [false, true] {
+                    let (first, last) = match reversed {
+                        false => (segment[0], segment[len - 1]),
+                        true => (segment[len - 1], segment[0]),
+                    };
+                    // Change in cost from putting the run back in before the given position
+                    let mut insertion = 0;
+                    if position > 0 {
+                        insertion += costs[rest[position - 1]][first];
+                    }
+                    if position < rest.len() {
+                        insertion += costs[last][rest[position]];
+                    }
+                    if position > 0 && position < rest.len() {
+                        insertion -= costs[rest[position - 1]][rest[position]];
+                    }
+                    if removal + insertion < 0 {
+                        let mut moved = segment.clone();
+                        if reversed {
+                            moved.reverse();
+                        }
+                        *path = rest[..position]
+                            .iter()
+                            .chain(moved.iter())
+                            .chain(rest[position..].iter())
+                            .copied()
+                            .collect();
+                        return true;
+                    }
+                }
+            }
+        }
+    }
+    false
+}
+
+/// Finds the minimum and maximum distances needed to visit all nodes in the graph by trying every
+/// order of the nodes, kept as a reference for the Held-Karp solver. Returned value is a tuple
+/// containing the minimum and maximum distances found (in that order).
 fn find_min_max_distances_to_visit_all_nodes(
     edges: &HashMap<String, HashMap<String, u64>>,
 ) -> (Option<u64>, Option<u64>) {
@@ -151,4 +586,198 @@ mod test {
         let solution = solve_part2(&input);
         assert_eq!(736, solution);
     }
+
+    /// Builds the graph with the given roads between cities.
+    fn build_graph(roads: &[(&str, &str, u64)]) -> HashMap<String, HashMap<String, u64>> {
+        let mut edges: HashMap<String, HashMap<String, u64>> = HashMap::new();
+        for &(node1, node2, weight) in roads {
+            add_edges_between_nodes(&mut edges, node1, node2, weight);
+            add_edges_between_nodes(&mut edges, node2, node1, weight);
+        }
+        edges
+    }
+
+    /// Calculates the distance travelled along the route, checking that every city in the graph
+    /// is visited exactly once.
+    fn route_distance(edges: &HashMap<String, HashMap<String, u64>>, cities: &[String]) -> u64 {
+        assert_eq!(edges.len(), cities.len());
+        assert_eq!(edges.len(), cities.iter().unique().count());
+        cities
+            .windows(2)
+            .map(|pair| edges.get(&pair[0]).unwrap().get(&pair[1]).unwrap())
+            .sum()
+    }
+
+    /// Tests the Held-Karp solver against the example given in the problem description.
+    #[test]
+    fn test_day09_held_karp_example() {
+        let edges = build_graph(&[
+            ("London", "Dublin", 464),
+            ("London", "Belfast", 518),
+            ("Dublin", "Belfast", 141),
+        ]);
+        let (min_dist, min_route) = find_route_held_karp(&edges, Objective::Shortest).unwrap();
+        assert_eq!(605, min_dist);
+        assert_eq!(vec!["London", "Dublin", "Belfast"], min_route);
+        let (max_dist, max_route) = find_route_held_karp(&edges, Objective::Longest).unwrap();
+        assert_eq!(982, max_dist);
+        assert_eq!(982, route_distance(&edges, &max_route));
+    }
+
+    /// Tests the Held-Karp solver against the brute force search on the actual problem input.
+    #[test]
+    fn test_day09_held_karp_matches_brute_force() {
+        let input = process_input_file(PROBLEM_INPUT_FILE);
+        let (min_dist, max_dist) = find_min_max_distances_to_visit_all_nodes(&input);
+        for (objective, expected) in [
+            (Objective::Shortest, min_dist.unwrap()),
+            (Objective::Longest, max_dist.unwrap()),
+        ] {
+            let (dist, route) = find_route_held_karp(&input, objective).unwrap();
+            assert_eq!(expected, dist);
+            assert_eq!(dist, route_distance(&input, &route));
+        }
+    }
+
+    /// Tests the Held-Karp solver on graphs with missing roads and with more cities than the brute
+    /// force search could handle.
+    #[test]
+    fn test_day09_held_karp_large_and_sparse() {
+        // Cities along a line, so the shortest route runs from one end to the other and the
+        // longest route zig-zags between the ends
+        let names: Vec<String> = (0..16).map(|i| format!("City{:02}", i)).collect();
+        let roads: Vec<(&str, &str, u64)> = (0..16)
+            .tuple_combinations()
+            .map(|(i, j)| (names[i].as_str(), names[j].as_str(), (j - i) as u64 * 10))
+            .collect();
+        let edges = build_graph(&roads);
+        let (min_dist, min_route) = find_route_held_karp(&edges, Objective::Shortest).unwrap();
+        assert_eq!(150, min_dist);
+        assert_eq!(min_dist, route_distance(&edges, &min_route));
+        let (max_dist, max_route) = find_route_held_karp(&edges, Objective::Longest).unwrap();
+        assert_eq!(1270, max_dist);
+        assert_eq!(max_dist, route_distance(&edges, &max_route));
+        // Only route visiting every city in a chain of roads is along the chain
+        let chain = build_graph(&[("A", "B", 1), ("B", "C", 2), ("C", "D", 3)]);
+        let (dist, route) = find_route_held_karp(&chain, Objective::Longest).unwrap();
+        assert_eq!(6, dist);
+        assert_eq!(vec!["D", "C", "B", "A"], route);
+        // No route visits every city in a star of roads with three outer cities
+        let star = build_graph(&[("Hub", "A", 1), ("Hub", "B", 2), ("Hub", "C", 3)]);
+        assert_eq!(None, find_route_held_karp(&star, Objective::Shortest));
+    }
+
+    /// Builds the graph of cities at the given points, with roads between every pair of cities
+    /// with the Manhattan distance between their points.
+    fn build_grid_graph(points: &[(u64, u64)]) -> HashMap<String, HashMap<String, u64>> {
+        let names: Vec<String> = (0..points.len()).map(|i| format!("City{:03}", i)).collect();
+        let roads: Vec<(&str, &str, u64)> = (0..points.len())
+            .tuple_combinations()
+            .map(|(i, j)| {
+                let ((x1, y1), (x2, y2)) = (points[i], points[j]);
+                (
+                    names[i].as_str(),
+                    names[j].as_str(),
+                    x1.abs_diff(x2) + y1.abs_diff(y2),
+                )
+            })
+            .collect();
+        build_graph(&roads)
+    }
+
+    /// Tests the heuristic route optimiser against the exact routes for the actual problem input.
+    #[test]
+    fn test_day09_heuristic_actual() {
+        let input = process_input_file(PROBLEM_INPUT_FILE);
+        for (objective, expected) in [(Objective::Shortest, 141), (Objective::Longest, 736)] {
+            let heuristic =
+                find_route_heuristic(&input, objective, DEFAULT_RESTARTS, DEFAULT_SEED).unwrap();
+            assert_eq!(Some(expected), heuristic.exact);
+            assert_eq!(expected, heuristic.route.0);
+            assert_eq!(Some(0), heuristic.gap());
+            assert_eq!(Some(0.0), heuristic.gap_percent());
+            assert_eq!(expected, route_distance(&input, &heuristic.route.1));
+        }
+    }
+
+    /// Tests that the heuristic route optimiser reports its gap against the exact route for small
+    /// graphs, and gives the same route for the same seed.
+    #[test]
+    fn test_day09_heuristic_gap() {
+        let mut rng = Xorshift::new(DEFAULT_SEED);
+        let points: Vec<(u64, u64)> = (0..12)
+            .map(|_| (rng.next_index(100) as u64, rng.next_index(100) as u64))
+            .collect();
+        let edges = build_grid_graph(&points);
+        for objective in [Objective::Shortest, Objective::Longest] {
+            let heuristic = find_route_heuristic(&edges, objective, 5, 7).unwrap();
+            let (exact, _) = find_route_held_karp(&edges, objective).unwrap();
+            let (dist, route) = &heuristic.route;
+            assert_eq!(*dist, route_distance(&edges, route));
+            assert_eq!(Some(exact), heuristic.exact);
+            let gap = dist.abs_diff(exact);
+            assert_eq!(Some(gap), heuristic.gap());
+            assert_eq!(
+                Some(gap as f64 / exact as f64 * 100.0),
+                heuristic.gap_percent()
+            );
+            assert!(!objective.improves(*dist, exact));
+            let repeat = find_route_heuristic(&edges, objective, 5, 7).unwrap();
+            assert_eq!(heuristic.route, repeat.route);
+        }
+        // No route visits every city in a star of roads with three outer cities
+        let star = build_graph(&[("Hub", "A", 1), ("Hub", "B", 2), ("Hub", "C", 3)]);
+        assert!(find_route_heuristic(&star, Objective::Shortest, 5, 7).is_none());
+        // No route is built without any restarts
+        assert!(find_route_heuristic(&edges, Objective::Shortest, 0, 7).is_none());
+    }
+
+    /// Tests the gap reported by the heuristic route optimiser for a single restart on a graph
+    /// where the route it finds is known to be suboptimal.
+    #[test]
+    fn test_day09_heuristic_known_gap() {
+        let edges = build_graph(&[
+            ("A", "B", 20),
+            ("A", "C", 13),
+            ("A", "D", 6),
+            ("A", "E", 3),
+            ("B", "C", 5),
+            ("B", "D", 19),
+            ("B", "E", 6),
+            ("C", "D", 17),
+            ("C", "E", 19),
+            ("D", "E", 8),
+        ]);
+        // Starting from B, the cheap road to C leads onto the long road from C to A, and no 2-opt
+        // or Or-opt move improves the route. Best route is C -> B -> E -> A -> D.
+        let heuristic = find_route_heuristic(&edges, Objective::Shortest, 1, 1).unwrap();
+        let expected_route = ["B", "C", "A", "E", "D"].map(String::from);
+        assert_eq!((29, expected_route.to_vec()), heuristic.route);
+        assert_eq!(Some(20), heuristic.exact);
+        assert_eq!(Some(9), heuristic.gap());
+        assert_eq!(Some(45.0), heuristic.gap_percent());
+    }
+
+    /// Tests the heuristic route optimiser on a graph too large for the Held-Karp solver.
+    #[test]
+    fn test_day09_heuristic_large() {
+        // Cities along a line, so the shortest route runs from one end to the other
+        let points: Vec<(u64, u64)> = (0..60).map(|i| ((i * 37) % 60, 0)).collect();
+        let edges = build_grid_graph(&points);
+        let heuristic = find_route_heuristic(&edges, Objective::Shortest, 3, DEFAULT_SEED).unwrap();
+        assert_eq!(59, heuristic.route.0);
+        assert_eq!(59, route_distance(&edges, &heuristic.route.1));
+        assert_eq!((None, None), (heuristic.exact, heuristic.gap()));
+        // Improvement never makes the nearest neighbour route worse
+        let (_, dist) = distance_matrix(&edges);
+        let costs: Vec<Vec<i64>> = dist
+            .iter()
+            .map(|row| row.iter().map(|&d| Objective::Longest.cost(d)).collect())
+            .collect();
+        let mut path = build_nearest_neighbour_route(&costs, 0, 1, &mut Xorshift::new(1));
+        let before = route_cost(&costs, &path);
+        improve_route(&costs, &mut path);
+        assert!(route_cost(&costs, &path) <= before);
+        assert_eq!(60, path.iter().unique().count());
+    }
 }
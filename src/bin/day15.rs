@@ -1,9 +1,10 @@
+use std::env;
 use std::fs;
 use std::time::Instant;
 
-use fancy_regex::{Captures, Regex};
-
-use aoc2015::utils::bespoke::Ingredient;
+use aoc2015::utils::bespoke::{
+    Ingredient, Recipe, RecipeExplanation, RecipeOptimiser, Relation, TeaspoonMove,
+};
 
 const PROBLEM_NAME: &str = "Science for Hungry People";
 const PROBLEM_INPUT_FILE: &str = "./input/day15.txt";
@@ -11,10 +12,32 @@ const PROBLEM_DAY: u64 = 15;
 
 const LIMIT_TSP: i64 = 100;
 const TARGET_CALORIES: i64 = 500;
+const SCORED_PROPERTIES: [&str; 4] = ["capacity", "durability", "flavor", "texture"];
+const DEFAULT_EXPLORE_COUNT: usize = 5;
 
 /// Processes the AOC 2015 Day 15 input file and solves both parts of the problem. Solutions are
 /// printed to stdout.
+///
+/// Giving "recipe" as the argument instead prints the quantity of each ingredient used in the
+/// highest-scoring recipes for both parts of the problem. Giving "explore [count]" prints the
+/// given number of highest-scoring recipes (5 by default) for both parts, explaining each score by
+/// its property subtotals and the effect of moving one teaspoon between ingredients.
 pub fn main() {
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(|arg| arg.as_str()) {
+        Some("recipe") => {
+            let input = process_input_file(PROBLEM_INPUT_FILE);
+            return print_recipes(&input);
+        }
+        Some("explore") => {
+            let count = args.get(2).map_or(DEFAULT_EXPLORE_COUNT, |arg| {
+                arg.parse::<usize>().expect("Invalid recipe count!")
+            });
+            let input = process_input_file(PROBLEM_INPUT_FILE);
+            return print_explanations(&input, count);
+        }
+        _ => (),
+    }
     let start = Instant::now();
     // Input processing
     let input = process_input_file(PROBLEM_INPUT_FILE);
@@ -46,36 +69,27 @@ pub fn main() {
 }
 
 /// Processes the AOC 2015 Day 15 input file into the format required by the solver functions.
-/// Returned value is vector of ingredients described in the inpu t file.
+/// Returned value is vector of ingredients described in the input file.
 fn process_input_file(filename: &str) -> Vec<Ingredient> {
     // Read contents of problem input file
     let raw_input = fs::read_to_string(filename).unwrap();
     // Process input file contents into data structure
-    let mut ingredients: Vec<Ingredient> = vec![];
-    let regex_line = Regex::new(concat!(
-        r#"^.*: capacity (-?\d+), durability (-?\d+), flavor (-?\d+), "#,
-        r#"texture (-?\d+), calories (-?\d+)$"#,
-    ))
-    .unwrap();
-    for line in raw_input.lines() {
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
-        }
-        if let Ok(Some(caps)) = regex_line.captures(line) {
-            ingredients.push(parse_captures_to_ingredient(&caps));
-        } else {
-            panic!("Bad format input line! // {line}");
-        }
-    }
-    ingredients
+    raw_input
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| match Ingredient::parse(line) {
+            Ok(ingredient) => ingredient,
+            Err(e) => panic!("Bad format input line! // {e}"),
+        })
+        .collect()
 }
 
 /// Solves AOC 2015 Day 15 Part 1 // Finds the total score of the higest-scoring cookie that can be
 /// made within the quantity limit.
 fn solve_part1(ingredients: &[Ingredient]) -> i64 {
-    if let Some(score) = find_highest_cookie_score(ingredients, false) {
-        score
+    if let Some(recipe) = find_best_recipe(ingredients, false) {
+        recipe.score()
     } else {
         panic!("Did not find the highest cookie score without calorie checking!");
     }
@@ -84,114 +98,122 @@ fn solve_part1(ingredients: &[Ingredient]) -> i64 {
 /// Solves AOC 2015 Day 15 Part 2 // Finds the total score of the highest-scoring cookie with the
 /// target calorie count that can be made from the ingredients.
 fn solve_part2(ingredients: &[Ingredient]) -> i64 {
-    if let Some(score) = find_highest_cookie_score(ingredients, true) {
-        score
+    if let Some(recipe) = find_best_recipe(ingredients, true) {
+        recipe.score()
     } else {
         panic!("Did not find the highest cookie score with calorie checking!");
     }
 }
 
-/// Parses the given regex captures into an ingredient. Assumption is that the captures result from
-/// regex matching the input file line format for an ingredient.
-fn parse_captures_to_ingredient(caps: &Captures) -> Ingredient {
-    let capacity = caps[1].parse::<i64>().unwrap();
-    let durability = caps[2].parse::<i64>().unwrap();
-    let flavour = caps[3].parse::<i64>().unwrap();
-    let texture = caps[4].parse::<i64>().unwrap();
-    let calories = caps[5].parse::<i64>().unwrap();
-    Ingredient::new(capacity, durability, flavour, texture, calories)
+/// Prints the quantity of each ingredient used in the highest-scoring recipes, with and without
+/// calorie checking.
+fn print_recipes(ingredients: &[Ingredient]) {
+    for (check_calories, title) in [(false, "Part 1"), (true, "Part 2")] {
+        match find_best_recipe(ingredients, check_calories) {
+            Some(recipe) => {
+                println!("[+] {}: score {}", title, recipe.score());
+                for (ingredient, tsp) in ingredients.iter().zip(recipe.quantities()) {
+                    println!("    {:>3} tsp {}", tsp, ingredient.name());
+                }
+            }
+            None => println!("[!] {}: no valid recipe", title),
+        }
+    }
 }
 
-/// Finds the highest cookie score possible with the ingredients and quantity limit.
-fn find_highest_cookie_score(ingredients: &[Ingredient], check_calories: bool) -> Option<i64> {
-    let mut max_score: Option<i64> = None;
-    find_highest_cookie_score_recursive(ingredients, &vec![], 0, check_calories, &mut max_score);
-    max_score
+/// Prints the given number of highest-scoring recipes, with and without calorie checking. Each
+/// recipe is explained by its property subtotals (noting those clamped to zero) and the best and
+/// worst moves of one teaspoon between ingredients.
+fn print_explanations(ingredients: &[Ingredient], count: usize) {
+    for (check_calories, title) in [(false, "Part 1"), (true, "Part 2")] {
+        let explanations = recipe_optimiser(ingredients, check_calories).explore(count);
+        if explanations.is_empty() {
+            println!("[!] {}: no valid recipe", title);
+        }
+        for (rank, explanation) in explanations.iter().enumerate() {
+            print_explanation(ingredients, title, rank + 1, explanation);
+        }
+    }
 }
 
-/// Recursive helper function to find the highest cookie score possible with the ingredients and
-/// quantity limit. Calorie checking is optional.
-fn find_highest_cookie_score_recursive(
+/// Prints the explanation of a single ranked recipe.
+fn print_explanation(
     ingredients: &[Ingredient],
-    quantities: &Vec<i64>,
-    running_total: i64,
-    check_calories: bool,
-    max_score: &mut Option<i64>,
+    title: &str,
+    rank: usize,
+    explanation: &RecipeExplanation,
 ) {
-    // Check if the enough quantity values have been found
-    if quantities.len() == ingredients.len() {
-        calculate_cookie_score(quantities, ingredients, check_calories, max_score);
-        return;
-    }
-    // Find collection of quantity values all less that limit that add up to exactly the limit
-    for tsp in 0..=LIMIT_TSP {
-        if tsp + running_total > LIMIT_TSP {
-            return;
+    let recipe = explanation.recipe();
+    println!("[+] {} #{}: score {}", title, rank, recipe.score());
+    let amounts: Vec<String> = ingredients
+        .iter()
+        .zip(recipe.quantities())
+        .map(|(ingredient, tsp)| format!("{} tsp {}", tsp, ingredient.name()))
+        .collect();
+    println!("    Recipe:    {}", amounts.join(", "));
+    let subtotals: Vec<String> = explanation
+        .subtotals()
+        .iter()
+        .map(|subtotal| match subtotal.is_clamped() {
+            true => format!(
+                "{} {} (clamped to 0)",
+                subtotal.property(),
+                subtotal.total()
+            ),
+            false => format!("{} {}", subtotal.property(), subtotal.total()),
+        })
+        .collect();
+    println!("    Subtotals: {}", subtotals.join(", "));
+    let describe = |teaspoon_move: &TeaspoonMove| {
+        format!(
+            "1 tsp {} -> {} ({:+})",
+            ingredients[teaspoon_move.from()].name(),
+            ingredients[teaspoon_move.to()].name(),
+            teaspoon_move.change()
+        )
+    };
+    match (explanation.best_move(), explanation.worst_move()) {
+        (Some(best), Some(worst)) => {
+            println!("    Best move:  {}", describe(best));
+            println!("    Worst move: {}", describe(worst));
+        }
+        _ => {
+            // Report moves ignoring the constraints, so the sensitivity of the score is still shown
+            let moves = explanation.moves();
+            let best = moves.iter().rev().max_by_key(|m| m.change());
+            let worst = moves.iter().min_by_key(|m| m.change());
+            if let (Some(best), Some(worst)) = (best, worst) {
+                println!("    Best move:  {} [breaks constraints]", describe(best));
+                println!("    Worst move: {} [breaks constraints]", describe(worst));
+            }
         }
-        let mut quantities = quantities.clone();
-        quantities.push(tsp);
-        find_highest_cookie_score_recursive(
-            ingredients,
-            &quantities,
-            running_total + tsp,
-            check_calories,
-            max_score,
-        );
     }
 }
 
-/// Calculates the current cookie score and updates the max score if a new maximum cookie score is
-/// found. Calorie checking is only performed to determine a valid cookie if check_calories is set
-/// to true.
-fn calculate_cookie_score(
-    quantities: &[i64],
-    ingredients: &[Ingredient],
-    check_calories: bool,
-    max_score: &mut Option<i64>,
-) {
-    // Calculate cookie component scores
-    let mut capacity_score = 0;
-    let mut durability_score = 0;
-    let mut flavour_score = 0;
-    let mut texture_score = 0;
-    let mut calorie_score = 0;
-    for (i, tsp) in quantities.iter().enumerate() {
-        capacity_score += tsp * ingredients[i].capacity();
-        durability_score += tsp * ingredients[i].durability();
-        flavour_score += tsp * ingredients[i].flavour();
-        texture_score += tsp * ingredients[i].texture();
-        calorie_score += tsp * ingredients[i].calories();
-    }
-    // Ensure that component scores are set to 0 if they are initially below 0
-    if capacity_score < 0 {
-        capacity_score = 0;
-    }
-    if durability_score < 0 {
-        durability_score = 0;
-    }
-    if flavour_score < 0 {
-        flavour_score = 0;
-    }
-    if texture_score < 0 {
-        texture_score = 0;
-    }
-    // Check if a new maximum cookie score has been found
-    let cookie_score = capacity_score * durability_score * flavour_score * texture_score;
-    if check_calories && calorie_score != TARGET_CALORIES {
-        return;
-    }
-    if let Some(score) = max_score {
-        if cookie_score < *score {
-            return;
-        }
+/// Finds the highest-scoring recipe that can be made from the ingredients within the quantity
+/// limit. Calorie checking is optional.
+fn find_best_recipe(ingredients: &[Ingredient], check_calories: bool) -> Option<Recipe> {
+    recipe_optimiser(ingredients, check_calories).optimise()
+}
+
+/// Creates the optimiser for recipes made from the ingredients within the quantity limit. Calorie
+/// checking is optional.
+fn recipe_optimiser(ingredients: &[Ingredient], check_calories: bool) -> RecipeOptimiser {
+    let optimiser = RecipeOptimiser::new(ingredients, &SCORED_PROPERTIES, LIMIT_TSP).unwrap();
+    match check_calories {
+        true => optimiser
+            .with_property("calories", Relation::Equal, TARGET_CALORIES)
+            .unwrap(),
+        false => optimiser,
     }
-    *max_score = Some(cookie_score);
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    use aoc2015::utils::bespoke::LinearConstraint;
+
     /// Tests the Day 15 Part 1 solver method against the actual problem solution.
     #[test]
     fn test_day15_part1_actual() {
@@ -207,4 +229,177 @@ mod test {
         let solution = solve_part2(&input);
         assert_eq!(11171160, solution);
     }
+
+    /// Tests the recipe optimiser against the example given in the problem description.
+    #[test]
+    fn test_day15_example() {
+        let ingredients = vec![
+            Ingredient::parse(
+                "Butterscotch: capacity -1, durability -2, flavor 6, texture 3, calories 8",
+            )
+            .unwrap(),
+            Ingredient::parse(
+                "Cinnamon: capacity 2, durability 3, flavor -2, texture -1, calories 3",
+            )
+            .unwrap(),
+        ];
+        let part1 = find_best_recipe(&ingredients, false).unwrap();
+        assert_eq!(62842880, part1.score());
+        assert_eq!(vec![44, 56], part1.quantities());
+        let part2 = find_best_recipe(&ingredients, true).unwrap();
+        assert_eq!(57600000, part2.score());
+        assert_eq!(vec![40, 60], part2.quantities());
+    }
+
+    /// Tests the recipe optimiser with ingredient bounds and a general linear constraint, checking
+    /// the result against every possible recipe.
+    #[test]
+    fn test_day15_constrained_recipe() {
+        let input = process_input_file(PROBLEM_INPUT_FILE);
+        let optimiser = RecipeOptimiser::new(&input, &SCORED_PROPERTIES, 40)
+            .unwrap()
+            .with_ingredient_bound("Sprinkles", Relation::AtMost, 10)
+            .unwrap()
+            .with_ingredient_bound("Sugar", Relation::AtLeast, 8)
+            .unwrap()
+            .with_constraint(LinearConstraint::new(&[0, 1, -1, 0], Relation::AtLeast, 2))
+            .unwrap();
+        let recipe = optimiser.optimise().unwrap();
+        assert!(optimiser.is_feasible(recipe.quantities()));
+        let mut best = 0;
+        for a in 0..=40 {
+            for b in 0..=40 - a {
+                for c in 0..=40 - a - b {
+                    let quantities = [a, b, c, 40 - a - b - c];
+                    if optimiser.is_feasible(&quantities) {
+                        best = best.max(optimiser.score(&quantities));
+                    }
+                }
+            }
+        }
+        assert_eq!(best, recipe.score());
+        // Recipes that cannot meet the constraints are not found
+        let impossible = optimiser
+            .with_ingredient_bound("Sugar", Relation::Equal, 41)
+            .unwrap();
+        assert_eq!(None, impossible.optimise());
+        assert!(RecipeOptimiser::new(&input, &["sweetness"], 100).is_err());
+    }
+
+    /// Tests the recipe optimiser with more ingredients than the exhaustive search could handle.
+    #[test]
+    fn test_day15_many_ingredients() {
+        let mut ingredients = process_input_file(PROBLEM_INPUT_FILE);
+        for line in [
+            "Butterscotch: capacity -1, durability -2, flavor 6, texture 3, calories 8",
+            "Cinnamon: capacity 2, durability 3, flavor -2, texture -1, calories 3",
+            "Candy: capacity 0, durability 0, flavor -1, texture 3, calories 2",
+            "Chocolate: capacity 3, durability 1, flavor 2, texture -2, calories 4",
+        ] {
+            ingredients.push(Ingredient::parse(line).unwrap());
+        }
+        let part1 = find_best_recipe(&ingredients, false).unwrap();
+        assert_eq!(66500544, part1.score());
+        assert_eq!(vec![6, 0, 0, 0, 42, 52, 0, 0], part1.quantities());
+        let part2 = find_best_recipe(&ingredients, true).unwrap();
+        assert_eq!(63926100, part2.score());
+        assert_eq!(vec![8, 5, 0, 0, 39, 47, 1, 0], part2.quantities());
+    }
+
+    /// Tests the ranking of recipes against every possible recipe made from the ingredients, with
+    /// and without calorie checking.
+    #[test]
+    fn test_day15_top_recipes() {
+        let input = process_input_file(PROBLEM_INPUT_FILE);
+        for check_calories in [false, true] {
+            let optimiser = recipe_optimiser(&input, check_calories);
+            let top = optimiser.top_recipes(10);
+            let mut scores: Vec<i64> = vec![];
+            for a in 0..=LIMIT_TSP {
+                for b in 0..=LIMIT_TSP - a {
+                    for c in 0..=LIMIT_TSP - a - b {
+                        let quantities = [a, b, c, LIMIT_TSP - a - b - c];
+                        if optimiser.is_feasible(&quantities) {
+                            scores.push(optimiser.score(&quantities));
+                        }
+                    }
+                }
+            }
+            scores.sort_unstable_by(|x, y| y.cmp(x));
+            let top_scores: Vec<i64> = top.iter().map(|recipe| recipe.score()).collect();
+            assert_eq!(scores[..10], top_scores);
+            for (i, recipe) in top.iter().enumerate() {
+                assert!(optimiser.is_feasible(recipe.quantities()));
+                assert!(top[..i]
+                    .iter()
+                    .all(|r| r.quantities() != recipe.quantities()));
+            }
+            assert_eq!(Some(&top[0]), optimiser.optimise().as_ref());
+        }
+        assert!(recipe_optimiser(&input, false).top_recipes(0).is_empty());
+    }
+
+    /// Tests the explanation of recipes from the example given in the problem description, covering
+    /// property subtotals, clamping and the sensitivity to moving one teaspoon.
+    #[test]
+    fn test_day15_recipe_explanation() {
+        let ingredients = vec![
+            Ingredient::parse(
+                "Butterscotch: capacity -1, durability -2, flavor 6, texture 3, calories 8",
+            )
+            .unwrap(),
+            Ingredient::parse(
+                "Cinnamon: capacity 2, durability 3, flavor -2, texture -1, calories 3",
+            )
+            .unwrap(),
+        ];
+        let explanations = recipe_optimiser(&ingredients, false).explore(3);
+        assert_eq!(3, explanations.len());
+        let best = &explanations[0];
+        assert_eq!(vec![44, 56], best.recipe().quantities());
+        let subtotals: Vec<(&str, i64)> = best
+            .subtotals()
+            .iter()
+            .map(|subtotal| (subtotal.property(), subtotal.total()))
+            .collect();
+        assert_eq!(
+            vec![
+                ("capacity", 68),
+                ("durability", 80),
+                ("flavor", 152),
+                ("texture", 76)
+            ],
+            subtotals
+        );
+        assert!(best.clamped_properties().is_empty());
+        // Moving a teaspoon either way from the best recipe lowers the score
+        assert_eq!(2, best.moves().len());
+        let scores: Vec<i64> = explanations[1..]
+            .iter()
+            .map(|explanation| explanation.recipe().score())
+            .collect();
+        for teaspoon_move in best.moves() {
+            assert!(teaspoon_move.is_feasible());
+            assert!(teaspoon_move.change() < 0);
+            assert!(scores.contains(&teaspoon_move.score()));
+        }
+        // Recipe with no cinnamon has negative capacity and durability, so scores zero
+        let optimiser = recipe_optimiser(&ingredients, false);
+        let explanation = RecipeExplanation::new(&optimiser, &[100, 0]);
+        assert_eq!(0, explanation.recipe().score());
+        assert_eq!(
+            vec!["capacity", "durability"],
+            explanation.clamped_properties()
+        );
+        assert_eq!(1, explanation.moves().len());
+        let best_move = explanation.best_move().unwrap();
+        assert_eq!(
+            (0, 1, 0),
+            (best_move.from(), best_move.to(), best_move.change())
+        );
+        // Moves breaking the calorie target are reported but are not feasible
+        let calories = recipe_optimiser(&ingredients, true).explore(1);
+        assert!(calories[0].moves().iter().all(|m| !m.is_feasible()));
+        assert_eq!(None, calories[0].best_move());
+    }
 }
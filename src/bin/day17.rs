@@ -1,6 +1,4 @@
-use std::cmp::Ordering;
-use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::env;
 use std::fs;
 use std::time::Instant;
 
@@ -12,7 +10,23 @@ const TARGET_TOTAL: u64 = 150; // litres
 
 /// Processes the AOC 2015 Day 17 input file and solves both parts of the problem. Solutions are
 /// printed to stdout.
+///
+/// Giving "combinations <count> [target]" as the arguments instead prints each combination of the
+/// given number of containers that holds the target total (150 litres by default), one per line.
 pub fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.get(1).map(|arg| arg.as_str()) == Some("combinations") {
+        let count = args
+            .get(2)
+            .expect("Container count not given!")
+            .parse::<usize>()
+            .expect("Invalid container count!");
+        let target = args.get(3).map_or(TARGET_TOTAL, |arg| {
+            arg.parse::<u64>().expect("Invalid target!")
+        });
+        let input = process_input_file(PROBLEM_INPUT_FILE);
+        return print_combinations(&input, target, count);
+    }
     let start = Instant::now();
     // Input processing
     let input = process_input_file(PROBLEM_INPUT_FILE);
@@ -59,68 +73,134 @@ fn process_input_file(filename: &str) -> Vec<u64> {
 /// Solves AOC 2015 Day 17 Part 1 // Finds the total number of ways to reach the target total of 150
 /// from the given values.
 fn solve_part1(values: &[u64]) -> u64 {
-    let (total, _) = find_subsets_adding_to_total(values);
+    let (total, _) = find_subsets_adding_to_total(values, TARGET_TOTAL);
     total
 }
 
 /// Solves AOC 2015 Day 17 Part 2 // Finds the number of ways that the target total can be reached
 /// using the minimum possible number of terms from the given values.
 fn solve_part2(values: &[u64]) -> u64 {
-    let (_, min_terms_count) = find_subsets_adding_to_total(values);
+    let (_, min_terms_count) = find_subsets_adding_to_total(values, TARGET_TOTAL);
     min_terms_count
 }
 
+/// Prints each combination of the given number of containers that holds the target total, giving
+/// the sizes of the containers used.
+fn print_combinations(values: &[u64], target: u64, count: usize) {
+    let mut found = 0;
+    for combination in SubsetCombinations::new(values, target, count) {
+        let sizes: Vec<String> = combination.iter().map(|&i| values[i].to_string()).collect();
+        println!("{}", sizes.join(" + "));
+        found += 1;
+    }
+    println!(
+        "[*] {} combination(s) of {} container(s) holding {} litres",
+        found, count, target
+    );
+}
+
 /// Finds the total number of subsets of the given values that add up to the target total, and the
-/// number of ways to reach the target total with the minimum number of terms.
-fn find_subsets_adding_to_total(values: &[u64]) -> (u64, u64) {
-    let mut container_counts: HashMap<u64, u64> = HashMap::new();
-    find_subsets_adding_to_total_recursive(values, 0, 0, 0, &mut container_counts);
-    let total: u64 = container_counts.values().sum();
-    let min_terms: u64 = *container_counts.keys().min().unwrap();
-    let min_terms_count: u64 = *container_counts.get(&min_terms).unwrap();
+/// number of ways to reach the target total with the minimum number of terms. Both are zero if the
+/// target total cannot be reached.
+fn find_subsets_adding_to_total(values: &[u64], target: u64) -> (u64, u64) {
+    let counts = count_subsets_by_size(values, target);
+    let total = counts
+        .iter()
+        .fold(0u64, |acc, &count| acc.saturating_add(count));
+    let min_terms_count = counts.into_iter().find(|&count| count > 0).unwrap_or(0);
     (total, min_terms_count)
 }
 
-/// Recursive helper function used to find subsets of the given values vector that add up to the
-/// target total.
-fn find_subsets_adding_to_total_recursive(
-    values: &[u64],
-    i: usize,
-    num_terms: u64,
-    running_total: u64,
-    container_counts: &mut HashMap<u64, u64>,
-) {
-    match running_total.cmp(&TARGET_TOTAL) {
-        Ordering::Less => {
-            // Stop if all values have been considered without reaching the target total exactly
-            if i >= values.len() {
-                return;
+/// Counts the subsets of the given values that add up to the target total, indexed by the number of
+/// terms in the subset. Uses dynamic programming over (running total, number of terms), so runs in
+/// pseudo-polynomial time rather than considering every subset. Counts saturate at u64::MAX.
+fn count_subsets_by_size(values: &[u64], target: u64) -> Vec<u64> {
+    let target = target as usize;
+    let n = values.len();
+    // ways[s][k] is number of subsets of the values considered so far with total s and k terms
+    let mut ways: Vec<Vec<u64>> = vec![vec![0; n + 1]; target + 1];
+    ways[0][0] = 1;
+    for (i, &value) in values.iter().enumerate() {
+        let value = value as usize;
+        if value > target {
+            continue;
+        }
+        // Work downwards so that each value is used at most once in each subset
+        for s in (value..=target).rev() {
+            for k in (1..=i + 1).rev() {
+                ways[s][k] = ways[s][k].saturating_add(ways[s - value][k - 1]);
+            }
+        }
+    }
+    ways.swap_remove(target)
+}
+
+/// Iterator over the combinations of a fixed number of values that add up to the target total.
+/// Each combination is given as the indices of the values used, in ascending order, so values of
+/// equal size are treated as distinct. Combinations are generated lazily in lexicographic order.
+struct SubsetCombinations<'a> {
+    values: &'a [u64],
+    target: u64,
+    size: usize,
+    picked: Vec<usize>,
+    total: u64,
+    next: usize, // index of the next value to consider adding
+    done: bool,
+}
+
+impl SubsetCombinations<'_> {
+    fn new(values: &[u64], target: u64, size: usize) -> SubsetCombinations<'_> {
+        SubsetCombinations {
+            values,
+            target,
+            size,
+            picked: vec![],
+            total: 0,
+            next: 0,
+            done: false,
+        }
+    }
+
+    /// Removes the most recently picked value and moves on to the value after it. The iterator is
+    /// exhausted once there are no picked values left to remove.
+    fn backtrack(&mut self) {
+        match self.picked.pop() {
+            Some(i) => {
+                self.total -= self.values[i];
+                self.next = i + 1;
             }
-            // Include the current term
-            find_subsets_adding_to_total_recursive(
-                values,
-                i + 1,
-                num_terms + 1,
-                running_total + values[i],
-                container_counts,
-            );
-            // Exclude the current term
-            find_subsets_adding_to_total_recursive(
-                values,
-                i + 1,
-                num_terms,
-                running_total,
-                container_counts,
-            );
+            None => self.done = true,
         }
-        Ordering::Equal => {
-            if let Entry::Vacant(e) = container_counts.entry(num_terms) {
-                e.insert(1);
+    }
+}
+
+impl Iterator for SubsetCombinations<'_> {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            if self.picked.len() == self.size {
+                let combination = match self.total == self.target {
+                    true => Some(self.picked.clone()),
+                    false => None,
+                };
+                self.backtrack();
+                if combination.is_some() {
+                    return combination;
+                }
+            } else if self.next + self.size - self.picked.len() > self.values.len() {
+                // Not enough values left to fill the combination
+                self.backtrack();
             } else {
-                *container_counts.get_mut(&num_terms).unwrap() += 1;
+                let i = self.next;
+                self.next += 1;
+                if self.total + self.values[i] <= self.target {
+                    self.picked.push(i);
+                    self.total += self.values[i];
+                }
             }
         }
-        Ordering::Greater => (),
+        None
     }
 }
 
@@ -143,4 +223,55 @@ mod test {
         let solution = solve_part2(&input);
         assert_eq!(17, solution);
     }
+
+    /// Tests the subset counter and combination iterator against the example given in the problem
+    /// description (containers of 20, 15, 10, 5 and 5 litres holding 25 litres).
+    #[test]
+    fn test_day17_example() {
+        let values = vec![20, 15, 10, 5, 5];
+        assert_eq!(vec![0, 0, 3, 1, 0, 0], count_subsets_by_size(&values, 25));
+        assert_eq!((4, 3), find_subsets_adding_to_total(&values, 25));
+        let pairs: Vec<Vec<usize>> = SubsetCombinations::new(&values, 25, 2).collect();
+        assert_eq!(vec![vec![0, 3], vec![0, 4], vec![1, 2]], pairs);
+        let triples: Vec<Vec<usize>> = SubsetCombinations::new(&values, 25, 3).collect();
+        assert_eq!(vec![vec![1, 3, 4]], triples);
+        assert_eq!((0, 0), find_subsets_adding_to_total(&values, 100));
+        assert_eq!(0, SubsetCombinations::new(&values, 100, 5).count());
+        assert_eq!(1, SubsetCombinations::new(&values, 0, 0).count());
+    }
+
+    /// Tests that the number of combinations given by the iterator matches the subset counts for
+    /// each number of terms.
+    #[test]
+    fn test_day17_combinations_match_counts() {
+        let input = process_input_file(PROBLEM_INPUT_FILE);
+        let counts = count_subsets_by_size(&input, TARGET_TOTAL);
+        for (size, &count) in counts.iter().enumerate() {
+            let combinations: Vec<Vec<usize>> =
+                SubsetCombinations::new(&input, TARGET_TOTAL, size).collect();
+            assert_eq!(count, combinations.len() as u64);
+            for combination in combinations {
+                let total: u64 = combination.iter().map(|&i| input[i]).sum();
+                assert_eq!(TARGET_TOTAL, total);
+            }
+        }
+    }
+
+    /// Tests the subset counter with hundreds of containers, where enumerating every subset would
+    /// not be feasible.
+    #[test]
+    fn test_day17_many_containers() {
+        // 300 containers of 1 litre holding 3 litres: C(300, 3) ways, all using 3 containers
+        let values = vec![1; 300];
+        assert_eq!((4455100, 4455100), find_subsets_adding_to_total(&values, 3));
+        // 200 containers of 2 litres and one of 1 litre holding 7 litres: 1 + 2 + 2 + 2 only
+        let mut values = vec![2; 200];
+        values.push(1);
+        assert_eq!(
+            vec![0, 0, 0, 0, 1313400],
+            count_subsets_by_size(&values, 7)[..5]
+        );
+        assert_eq!((1313400, 1313400), find_subsets_adding_to_total(&values, 7));
+        assert_eq!((0, 0), find_subsets_adding_to_total(&values[..200], 7));
+    }
 }
@@ -1,49 +1,50 @@
+use std::env;
 use std::fs;
 use std::time::Instant;
 
 use fancy_regex::Regex;
-use itertools::{iproduct, Itertools};
-use lazy_static::lazy_static;
 
-use aoc2015::utils::bespoke::{RpgEntity, RpgItem};
+use aoc2015::utils::bespoke::{
+    simulate_duel, Loadout, LoadoutAnalysis, LoadoutOutcome, RpgEntity, Shop,
+};
 
 const PROBLEM_NAME: &str = "RPG Simulator 20XX";
 const PROBLEM_INPUT_FILE: &str = "./input/day21.txt";
 const PROBLEM_DAY: u64 = 21;
+const SHOP_FILE: &str = "./input/day21_shop.txt";
 
 const PLAYER_START_HEALTH: i64 = 100;
+const MAX_DUEL_TURNS: u64 = 10000;
 
-lazy_static! {
-    /// Weapons held by store
-    static ref WEAPONS: Vec<RpgItem> = vec![
-        RpgItem::new(8, 4, 0),  // Dagger
-        RpgItem::new(10, 5, 0), // Shortsword
-        RpgItem::new(25, 6, 0), // Warhammer
-        RpgItem::new(40, 7, 0), // Longsword
-        RpgItem::new(74, 8, 0), // Greataxe
-    ];
-    /// Armour held by store
-    static ref ARMOUR: Vec<RpgItem> = vec![
-        RpgItem::new(13, 0, 1),     // Leather
-        RpgItem::new(31, 0, 2),     // Chainmail
-        RpgItem::new(53, 0, 3),     // Splitmail
-        RpgItem::new(75, 0, 4),     // Bandedmail
-        RpgItem::new(102, 0, 5),    // Platemail
-    ];
-    /// Rings held by store
-    static ref RINGS: Vec<RpgItem> = vec![
-        RpgItem::new(25, 1, 0),     // Damage +1
-        RpgItem::new(50, 2, 0),     // Damage +2
-        RpgItem::new(100, 3, 0),    // Damage +3
-        RpgItem::new(20, 0, 1),     // Defence +1
-        RpgItem::new(40, 0, 2),     // Defence +2
-        RpgItem::new(80, 0, 3),     // Defence +3
-    ];
-}
+/// Type definition to simplify signature of input file parser and solver functions.
+type ProblemInput = (RpgEntity, Shop);
 
 /// Processes the AOC 2015 Day 21 input file and solves both parts of the problem. Solutions are
 /// printed to stdout.
+///
+/// Giving "shop <shop_file>" as the arguments instead solves both parts of the problem using the
+/// items and slot rules from the given shop file, printing the items held in each solution. Giving
+/// "frontier [shop_file]" prints the cost-vs-margin frontier of the loadouts from the shop, and
+/// giving "duel [shop_file]" prints the round-by-round duel for the cheapest winning loadout.
 pub fn main() {
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(|arg| arg.as_str()) {
+        Some("shop") => {
+            let shop_file = args.get(2).expect("Shop file not given!");
+            let (enemy, _) = process_input_file(PROBLEM_INPUT_FILE);
+            return print_shop_solutions(&(enemy, load_shop(shop_file)));
+        }
+        Some(tool @ ("frontier" | "duel")) => {
+            let shop_file = args.get(2).map_or(SHOP_FILE, |arg| arg.as_str());
+            let (enemy, _) = process_input_file(PROBLEM_INPUT_FILE);
+            let input = (enemy, load_shop(shop_file));
+            return match tool {
+                "frontier" => print_frontier(&input),
+                _ => print_duel(&input),
+            };
+        }
+        _ => (),
+    }
     let start = Instant::now();
     // Input processing
     let input = process_input_file(PROBLEM_INPUT_FILE);
@@ -75,8 +76,9 @@ pub fn main() {
 }
 
 /// Processes the AOC 2015 Day 21 input file into the format required by the solver functions.
-/// Returned value is the RpgEntity representing the boss entity specified in the input file.
-fn process_input_file(filename: &str) -> RpgEntity {
+/// Returned value is tuple containing: the RpgEntity representing the boss entity specified in the
+/// input file, and the shop selling items to the player.
+fn process_input_file(filename: &str) -> ProblemInput {
     // Read contents of problem input file
     let raw_input = fs::read_to_string(filename).unwrap();
     // Process input file contents into data structure
@@ -86,85 +88,102 @@ fn process_input_file(filename: &str) -> RpgEntity {
         let damage = caps[2].parse::<i64>().unwrap();
         let armour = caps[3].parse::<i64>().unwrap();
         let enemy = RpgEntity::new(health, damage, armour);
-        return enemy;
+        return (enemy, load_shop(SHOP_FILE));
     }
     panic!("Invalid input file format!");
 }
 
+/// Loads the shop from the given shop listing file.
+fn load_shop(filename: &str) -> Shop {
+    let raw_input = fs::read_to_string(filename).unwrap();
+    match Shop::parse(&raw_input) {
+        Ok(shop) => shop,
+        Err(e) => panic!("Invalid shop file! {}", e),
+    }
+}
+
 /// Solves AOC 2015 Day 21 Part 1 // Determines the least amount of gold the player can spend and
 /// still win the fight.
-fn solve_part1(enemy: &RpgEntity) -> i64 {
-    let mut least_gold: Option<i64> = None;
-    for (q_armour, q_rings) in iproduct!(0..=1, 0..=2) {
-        for weapon_held in WEAPONS.iter() {
-            for armour_held in ARMOUR.iter().combinations(q_armour) {
-                for rings_held in RINGS.iter().combinations(q_rings) {
-                    let mut cost = 0;
-                    cost += weapon_held.cost();
-                    cost += armour_held.iter().map(|elem| elem.cost()).sum::<i64>();
-                    cost += rings_held.iter().map(|elem| elem.cost()).sum::<i64>();
-                    let damage = weapon_held.damage()
-                        + rings_held.iter().map(|elem| elem.damage()).sum::<i64>();
-                    let armour = armour_held.iter().map(|elem| elem.armour()).sum::<i64>()
-                        + rings_held.iter().map(|elem| elem.armour()).sum::<i64>();
-                    // Create player
-                    let player = RpgEntity::new(PLAYER_START_HEALTH, damage, armour);
-                    // Calculate turns to defeat
-                    let player_turns = player.turns_to_defeat(enemy);
-                    let enemy_turns = enemy.turns_to_defeat(&player);
-                    if player_turns <= enemy_turns
-                        && (least_gold.is_none() || cost < least_gold.unwrap())
-                    {
-                        least_gold = Some(cost);
-                    }
-                }
-            }
-        }
-    }
-    if let Some(cost) = least_gold {
-        return cost;
+fn solve_part1(input: &ProblemInput) -> i64 {
+    if let Some(outcome) = analyse_loadouts(input).cheapest_with_margin(0) {
+        return outcome.cost();
     }
     panic!("Did not find the least amount of gold with player win outcome!");
 }
 
 /// Solves AOC 2015 Day 21 Part 2 // Determines the most amount of gold the player can spend and
 /// still lose the fight.
-fn solve_part2(enemy: &RpgEntity) -> i64 {
-    let mut most_gold: Option<i64> = None;
-    for (q_armour, q_rings) in iproduct!(0..=1, 0..=2) {
-        for weapon_held in WEAPONS.iter() {
-            for armour_held in ARMOUR.iter().combinations(q_armour) {
-                for rings_held in RINGS.iter().combinations(q_rings) {
-                    let mut cost = 0;
-                    cost += weapon_held.cost();
-                    cost += armour_held.iter().map(|elem| elem.cost()).sum::<i64>();
-                    cost += rings_held.iter().map(|elem| elem.cost()).sum::<i64>();
-                    let damage = weapon_held.damage()
-                        + rings_held.iter().map(|elem| elem.damage()).sum::<i64>();
-                    let armour = armour_held.iter().map(|elem| elem.armour()).sum::<i64>()
-                        + rings_held.iter().map(|elem| elem.armour()).sum::<i64>();
-                    // Create player
-                    let player = RpgEntity::new(PLAYER_START_HEALTH, damage, armour);
-                    // Calculate turns to defeat
-                    let player_turns = player.turns_to_defeat(enemy);
-                    let enemy_turns = enemy.turns_to_defeat(&player);
-                    if player_turns > enemy_turns
-                        && (most_gold.is_none() || cost > most_gold.unwrap())
-                    {
-                        most_gold = Some(cost);
-                    }
-                }
-            }
-        }
+fn solve_part2(input: &ProblemInput) -> i64 {
+    if let Some(outcome) = analyse_loadouts(input).costliest_below_margin(0) {
+        return outcome.cost();
     }
-    if let Some(cost) = most_gold {
-        return cost;
+    panic!("Did not find the most amount of gold with player loss outcome!");
+}
+
+/// Analyses the outcome of the fight against the enemy for each loadout from the shop.
+fn analyse_loadouts(input: &ProblemInput) -> LoadoutAnalysis {
+    let (enemy, shop) = input;
+    LoadoutAnalysis::new(shop, PLAYER_START_HEALTH, enemy)
+}
+
+/// Describes the cost and margin of the loadout, along with the names of its items.
+fn describe_outcome(outcome: &LoadoutOutcome) -> String {
+    format!(
+        "{} gold, margin {} ({})",
+        outcome.cost(),
+        outcome.margin(),
+        outcome.item_names().join(", ")
+    )
+}
+
+/// Prints the solutions to both parts of the problem, along with the items held by the player.
+fn print_shop_solutions(input: &ProblemInput) {
+    let analysis = analyse_loadouts(input);
+    let describe = |outcome: Option<&LoadoutOutcome>| match outcome {
+        Some(outcome) => describe_outcome(outcome),
+        None => String::from("none"),
+    };
+    println!(
+        "[+] Least gold to win:  {}",
+        describe(analysis.cheapest_with_margin(0))
+    );
+    println!(
+        "[+] Most gold to lose:  {}",
+        describe(analysis.costliest_below_margin(0))
+    );
+}
+
+/// Prints the duel between the player holding the cheapest winning loadout and the enemy, in the
+/// narrative style used in the problem description.
+fn print_duel(input: &ProblemInput) {
+    let (enemy, _) = input;
+    let analysis = analyse_loadouts(input);
+    let outcome = match analysis.cheapest_with_margin(0) {
+        Some(outcome) => outcome,
+        None => return println!("Player is unable to defeat the enemy!"),
+    };
+    println!("Loadout: {}", describe_outcome(outcome));
+    let log = simulate_duel(&loadout_player(outcome.loadout()), enemy, MAX_DUEL_TURNS);
+    print!("{}", log.to_narrative());
+}
+
+/// Creates the player holding the loadout, including the health regenerated by its items.
+fn loadout_player(loadout: &Loadout) -> RpgEntity {
+    RpgEntity::new(PLAYER_START_HEALTH, loadout.damage(), loadout.armour())
+        .with_regen(loadout.regen())
+}
+
+/// Prints the cost-vs-margin frontier of the loadouts, in order of increasing cost.
+fn print_frontier(input: &ProblemInput) {
+    for outcome in analyse_loadouts(input).frontier() {
+        println!("{}", describe_outcome(outcome));
     }
-    panic!("Did not find the least amount of gold with player win outcome!");
 }
 
 #[cfg(test)]
 mod test {
+    use aoc2015::utils::bespoke::{DuelEvent, Duelist};
+
     use super::*;
 
     /// Tests the Day 21 Part 1 solver method against the actual problem solution.
@@ -182,4 +201,188 @@ mod test {
         let solution = solve_part2(&input);
         assert_eq!(148, solution);
     }
+
+    /// Tests that the loadouts from the shop file honour the slot rules of each category.
+    #[test]
+    fn test_day21_shop_loadouts() {
+        let shop = load_shop(SHOP_FILE);
+        let names: Vec<&str> = shop.categories().iter().map(|c| c.name()).collect();
+        assert_eq!(vec!["Weapons", "Armor", "Rings"], names);
+        // 5 weapons, 6 armour choices (including none) and 22 ring choices (0, 1 or 2 rings)
+        let loadouts = shop.loadouts();
+        assert_eq!(5 * 6 * 22, loadouts.len());
+        for loadout in loadouts.iter() {
+            let count = |category: usize| {
+                let items = shop.categories()[category].items();
+                loadout
+                    .items()
+                    .iter()
+                    .filter(|&i| items.contains(i))
+                    .count()
+            };
+            assert_eq!(1, count(0));
+            assert!(count(1) <= 1 && count(2) <= 2);
+        }
+    }
+
+    /// Tests solving with an alternative shop and slot rules, along with rejection of invalid
+    /// shop files.
+    #[test]
+    fn test_day21_custom_shop() {
+        let (enemy, _) = process_input_file(PROBLEM_INPUT_FILE);
+        let raw_shop = "Weapons [1-2]:  Cost  Damage  Armor
+            Club            5     3       0
+            Spear           15    5       0
+
+            Charms [0-1]:   Cost  Damage  Armor
+            Ward +4         60    0       4";
+        let shop = Shop::parse(raw_shop).unwrap();
+        assert_eq!(3 * 2, shop.loadouts().len());
+        let input = (enemy, shop);
+        let outcome = analyse_loadouts(&input)
+            .cheapest_with_margin(0)
+            .unwrap()
+            .clone();
+        assert_eq!(vec!["Club", "Spear", "Ward +4"], outcome.item_names());
+        assert_eq!(80, solve_part1(&input));
+        assert_eq!(75, solve_part2(&input));
+        assert!(Shop::parse("Dagger 8 4 0").is_err());
+        assert!(Shop::parse("Weapons [2-1]: Cost Damage Armor\nDagger 8 4 0").is_err());
+        assert!(Shop::parse("Weapons [2-2]: Cost Damage Armor\nDagger 8 4 0").is_err());
+        assert!(Shop::parse("Weapons [1-1]: Cost Damage Armor\nDagger 8 four 0").is_err());
+    }
+
+    /// Tests the cost-vs-margin frontier of the loadouts against the margins of every loadout.
+    #[test]
+    fn test_day21_loadout_frontier() {
+        let input = process_input_file(PROBLEM_INPUT_FILE);
+        let analysis = analyse_loadouts(&input);
+        assert_eq!(660, analysis.outcomes().len());
+        let frontier = analysis.frontier();
+        for pair in frontier.windows(2) {
+            assert!(pair[0].cost() < pair[1].cost() && pair[0].margin() < pair[1].margin());
+        }
+        // No loadout is as cheap as a frontier loadout and has a greater margin
+        for outcome in frontier.iter() {
+            assert!(analysis
+                .outcomes()
+                .iter()
+                .all(|other| other.cost() > outcome.cost() || other.margin() <= outcome.margin()));
+        }
+        // Cheapest win matches Part 1, and winning with turns to spare costs more
+        let cheapest_win = analysis.cheapest_with_margin(0).unwrap();
+        assert_eq!(78, cheapest_win.cost());
+        assert_eq!(
+            vec!["Longsword", "Leather", "Damage +1"],
+            cheapest_win.item_names()
+        );
+        let safe_win = analysis.cheapest_with_margin(2).unwrap();
+        assert!(safe_win.margin() >= 2 && safe_win.cost() > 78);
+        let expected = analysis
+            .outcomes()
+            .iter()
+            .filter(|outcome| outcome.margin() >= 2)
+            .map(|outcome| outcome.cost())
+            .min();
+        assert_eq!(expected, Some(safe_win.cost()));
+        assert_eq!(None, analysis.cheapest_with_margin(1000));
+    }
+
+    /// Tests the round-by-round duel against the example given in the problem description.
+    #[test]
+    fn test_day21_duel_example() {
+        let player = RpgEntity::new(8, 5, 5);
+        let boss = RpgEntity::new(12, 7, 2);
+        let log = simulate_duel(&player, &boss, MAX_DUEL_TURNS);
+        assert_eq!(Some(Duelist::Player), log.winner());
+        assert_eq!(7, log.turns());
+        let expected = "\
+- The player deals 5-2 = 3 damage; the boss goes down to 9 hit points.
+- The boss deals 7-5 = 2 damage; the player goes down to 6 hit points.
+- The player deals 5-2 = 3 damage; the boss goes down to 6 hit points.
+- The boss deals 7-5 = 2 damage; the player goes down to 4 hit points.
+- The player deals 5-2 = 3 damage; the boss goes down to 3 hit points.
+- The boss deals 7-5 = 2 damage; the player goes down to 2 hit points.
+- The player deals 5-2 = 3 damage; the boss goes down to 0 hit points.
+The player wins.
+";
+        assert_eq!(expected, log.to_narrative());
+        // Boss striking first wins the same fight
+        let log = simulate_duel(&player, &boss.with_first_strike(), MAX_DUEL_TURNS);
+        assert_eq!(Some(Duelist::Boss), log.winner());
+        assert!(!player.defeats(&boss.with_first_strike()));
+    }
+
+    /// Tests that the closed-form winner from the turns needed by each entity matches the winner of
+    /// the simulated duel, for randomly generated entities (without regeneration).
+    #[test]
+    fn test_day21_duel_closed_form_property() {
+        let mut state: u64 = 0x2015_0021;
+        let mut next = |max: u64| {
+            // Xorshift pseudo-random number generator
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % (max + 1)) as i64
+        };
+        for _ in 0..5000 {
+            let mut player = RpgEntity::new(1 + next(120), next(12), next(8));
+            let mut boss = RpgEntity::new(1 + next(120), next(12), next(8));
+            if next(3) == 0 {
+                player = player.with_first_strike();
+            }
+            if next(3) == 0 {
+                boss = boss.with_first_strike();
+            }
+            let log = simulate_duel(&player, &boss, MAX_DUEL_TURNS);
+            let expected = match player.defeats(&boss) {
+                true => Duelist::Player,
+                false => Duelist::Boss,
+            };
+            assert_eq!(Some(expected), log.winner(), "{:?} vs {:?}", player, boss);
+        }
+    }
+
+    /// Tests duels with items regenerating health, including a duel that can never be decided.
+    #[test]
+    fn test_day21_duel_regen() {
+        let (enemy, _) = process_input_file(PROBLEM_INPUT_FILE);
+        let raw_shop = "Weapons [1-1]:  Cost  Damage  Armor
+            Longsword       40    7       0
+
+            Charms [0-1]:   Cost  Damage  Armor  Regen
+            Amulet 2        30    0       0      2
+            Amulet 9        90    0       0      9";
+        let shop = Shop::parse(raw_shop).unwrap();
+        let loadouts = shop.loadouts();
+        let regens: Vec<i64> = loadouts.iter().map(|loadout| loadout.regen()).collect();
+        assert_eq!(vec![0, 2, 9], regens);
+        // Longsword alone loses, and regenerating 2 hit points per turn is not enough to survive
+        for loadout in loadouts[..2].iter() {
+            let log = simulate_duel(&loadout_player(loadout), &enemy, MAX_DUEL_TURNS);
+            assert_eq!(Some(Duelist::Boss), log.winner());
+        }
+        // Regenerating the full damage dealt by the boss outlasts the boss
+        let log = simulate_duel(&loadout_player(&loadouts[2]), &enemy, MAX_DUEL_TURNS);
+        assert_eq!(Some(Duelist::Player), log.winner());
+        assert!(log.events().iter().any(|event| matches!(
+            event,
+            DuelEvent::Regenerate {
+                duelist: Duelist::Player,
+                healed: 8,
+                ..
+            }
+        )));
+        // Regeneration cannot go above the starting health
+        assert!(log.events().iter().all(|event| match event {
+            DuelEvent::Regenerate { health, .. } => *health <= PLAYER_START_HEALTH,
+            _ => true,
+        }));
+        // Neither side can defeat the other when both regenerate all damage taken
+        let player = RpgEntity::new(10, 3, 0).with_regen(5);
+        let boss = RpgEntity::new(10, 3, 0).with_regen(5);
+        let log = simulate_duel(&player, &boss, 100);
+        assert_eq!(None, log.winner());
+        assert_eq!(100, log.turns());
+    }
 }
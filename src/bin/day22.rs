@@ -1,22 +1,52 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::env;
 use std::fs;
 use std::time::Instant;
 
 use fancy_regex::Regex;
-use strum::IntoEnumIterator;
 
-use aoc2015::utils::bespoke::{MagicEntity, Spell};
+use aoc2015::utils::bespoke::{
+    play_round, simulate_fight, Difficulty, Encounter, EncounterState, MagicEntity, RoundOutcome,
+    Spell, Spellbook,
+};
 
 const PROBLEM_NAME: &str = "Wizard Simulator 20XX";
 const PROBLEM_INPUT_FILE: &str = "./input/day22.txt";
 const PROBLEM_DAY: u64 = 22;
+const SPELLBOOK_FILE: &str = "./input/day22_spellbook.json";
 
 // Player starting values
 const PLAYER_HEALTH: i64 = 50;
 const PLAYER_MANA: i64 = 500;
 
+/// Type definition to simplify signature of input file parser and solver functions.
+type ProblemInput = (MagicEntity, Spellbook);
+
+/// Type definition for a fight state reached in the search: the encounter state at the start of
+/// the player turn, with the index of the previous state and the spell (and target) cast to reach
+/// it.
+type FightState<'a> = (EncounterState, Option<(usize, &'a Spell, usize)>);
+
 /// Processes the AOC 2015 Day 22 input file and solves both parts of the problem. Solutions are
 /// printed to stdout.
+///
+/// Giving "log [options]" as the arguments instead prints the battle log of the fight won with the
+/// minimum amount of mana, in the narrative style (or JSON format if the "json" option is given).
+/// The difficulty is easy unless modified by the following options:
+/// - "hard" uses the HARD difficulty.
+/// - "drain=<health>" sets the health lost by the player at the start of each player turn.
+/// - "boss_damage=<percent>" sets the boss damage as a percentage of its base damage.
+/// - "mana_cap=<mana>" sets the maximum amount of mana the player can hold.
+/// - "start=<spell>" starts the fight with the spell effect active on the player.
 pub fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.get(1).map(|arg| arg.as_str()) == Some("log") {
+        let input = process_input_file(PROBLEM_INPUT_FILE);
+        let options: Vec<&str> = args[2..].iter().map(|arg| arg.as_str()).collect();
+        let difficulty = parse_difficulty(&options, &input.1);
+        return print_battle_log(&input, &difficulty, options.contains(&"json"));
+    }
     let start = Instant::now();
     // Input processing
     let input = process_input_file(PROBLEM_INPUT_FILE);
@@ -48,9 +78,9 @@ pub fn main() {
 }
 
 /// Processes the AOC 2015 Day 22 input file into the format required by the solver functions.
-/// Returned value is MagicEntity specified by the health and damage points in the input file
-/// (armour and mana are set to 0).
-fn process_input_file(filename: &str) -> MagicEntity {
+/// Returned value is tuple containing: MagicEntity specified by the health and damage points in the
+/// input file (armour and mana are set to 0), and the spellbook available to the player.
+fn process_input_file(filename: &str) -> ProblemInput {
     // Read contents of problem input file
     let raw_input = fs::read_to_string(filename).unwrap();
     // Process input file contents into data structure
@@ -58,16 +88,30 @@ fn process_input_file(filename: &str) -> MagicEntity {
     if let Ok(Some(caps)) = input_regex.captures(&raw_input) {
         let health = caps[1].parse::<i64>().unwrap();
         let damage = caps[2].parse::<i64>().unwrap();
-        return MagicEntity::new(health, damage, 0, 0);
+        return (
+            MagicEntity::new(health, damage, 0, 0),
+            load_spellbook(SPELLBOOK_FILE),
+        );
     }
     panic!("Invalid format input file!")
 }
 
+/// Loads the spellbook from the given JSON spellbook file.
+fn load_spellbook(filename: &str) -> Spellbook {
+    let raw_input = fs::read_to_string(filename).unwrap();
+    match Spellbook::from_json(&raw_input) {
+        Ok(spellbook) => spellbook,
+        Err(e) => panic!("Invalid spellbook file! {}", e),
+    }
+}
+
 /// Solves AOC 2015 Day 22 Part 1 // Determines the minimum amount of mana needed for the player to
 /// defeat the enemy in Wizard Simulator 20XX (easy mode).
-fn solve_part1(enemy: &MagicEntity) -> i64 {
+fn solve_part1(input: &ProblemInput) -> i64 {
+    let (enemy, spellbook) = input;
     let player = &MagicEntity::new(PLAYER_HEALTH, 0, 0, PLAYER_MANA);
-    if let Some(min_mana) = conduct_fight(player, enemy, false) {
+    if let Some((min_mana, _spells)) = conduct_fight(player, enemy, spellbook, &Difficulty::easy())
+    {
         return min_mana;
     }
     panic!("Player was unable to defeat the enemy on easy mode!");
@@ -75,90 +119,156 @@ fn solve_part1(enemy: &MagicEntity) -> i64 {
 
 /// Solves AOC 2015 Day 22 Part 2 // Determines the minimum amount of mana needed for the player to
 /// defeat the enemy in Wizard Simulator 20XX (HARD mode).
-fn solve_part2(enemy: &MagicEntity) -> i64 {
+fn solve_part2(input: &ProblemInput) -> i64 {
+    let (enemy, spellbook) = input;
     let player = &MagicEntity::new(PLAYER_HEALTH, 0, 0, PLAYER_MANA);
-    if let Some(min_mana) = conduct_fight(player, enemy, true) {
+    if let Some((min_mana, _spells)) = conduct_fight(player, enemy, spellbook, &Difficulty::hard())
+    {
         return min_mana;
     }
     panic!("Player was unable to defeat the enemy on HARD mode!");
 }
 
-/// Conducts the fight between the player and the enemy. Returns an Option containing the minimum
-/// amount of mana needed by the player to defeat the enemy. Returned value is None if the player
+/// Conducts the fight between the player and the enemy, with the player casting spells from the
+/// spellbook. Returns an Option containing the minimum amount of mana needed by the player to
+/// defeat the enemy and the sequence of spells cast to do so. Returned value is None if the player
 /// is unable to defeat the enemy.
-fn conduct_fight(player: &MagicEntity, enemy: &MagicEntity, hard_mode: bool) -> Option<i64> {
-    let mut min_mana: Option<i64> = None;
-    conduct_fight_recursive(player, enemy, hard_mode, &mut min_mana);
-    min_mana
+fn conduct_fight(
+    player: &MagicEntity,
+    enemy: &MagicEntity,
+    spellbook: &Spellbook,
+    difficulty: &Difficulty,
+) -> Option<(i64, Vec<Spell>)> {
+    let encounter = Encounter::single(enemy);
+    let (min_mana, casts) = conduct_encounter(player, &encounter, spellbook, difficulty)?;
+    Some((
+        min_mana,
+        casts.into_iter().map(|(spell, _)| spell).collect(),
+    ))
 }
 
-fn conduct_fight_recursive(
+/// Conducts the encounter between the player and the enemies, with the player casting spells from
+/// the spellbook at a chosen enemy, using a best-first search over the fight states (ordered by
+/// mana spent). Fight states are hashed directly, so states already reached with less mana spent
+/// are skipped. Returns an Option containing the minimum amount of mana needed by the player to
+/// defeat the enemies and the sequence of spells cast to do so (with the index of the enemy
+/// targeted by each spell). Returned value is None if the player is unable to defeat the enemies.
+fn conduct_encounter(
     player: &MagicEntity,
-    enemy: &MagicEntity,
-    hard_mode: bool,
-    min_mana: &mut Option<i64>,
-) {
-    for spell in Spell::iter() {
-        // Clone the player and the enemy
-        let mut player = player.clone();
-        let mut enemy = enemy.clone();
-        // Player turn
-        // // Apply hard mode damage and check if player is dead
-        if hard_mode {
-            player.deal_damage(1, true);
-        }
-        if player.is_dead() {
-            return;
-        }
-        // // Process player effects then check if enemy is dead
-        player.process_effects(&mut enemy);
-        if enemy.is_dead() {
-            update_min_mana(min_mana, &player);
-            return;
-        }
-        // // Continue to next spell if player cannot cast the spell
-        if !player.can_cast(spell) {
-            return;
-        }
-        if player.is_effect_active(spell) {
-            continue;
+    encounter: &Encounter,
+    spellbook: &Spellbook,
+    difficulty: &Difficulty,
+) -> Option<(i64, Vec<(Spell, usize)>)> {
+    let start = encounter.start(player, difficulty);
+    let mut queue: BinaryHeap<Reverse<(i64, usize)>> = BinaryHeap::new();
+    queue.push(Reverse((start.player().total_mana_spent(), 0)));
+    let mut states: Vec<FightState> = vec![(start, None)];
+    let mut winning_states: HashSet<usize> = HashSet::new();
+    let mut min_mana_spent: HashMap<EncounterState, i64> = HashMap::new();
+    while let Some(Reverse((mana_spent, index))) = queue.pop() {
+        if winning_states.contains(&index) {
+            return Some((mana_spent, spell_sequence(&states, index)));
         }
-        // // Cast the spell
-        match player.cast_spell(spell, &mut enemy, false) {
-            Ok(_) => (),
-            Err(_message) => continue,
+        for spell in spellbook.spells() {
+            for target in spell_targets(&states[index].0, spell) {
+                let mut state = states[index].0.clone();
+                let outcome = play_round(encounter, &mut state, spell, target, difficulty, None);
+                let mana = state.player().total_mana_spent();
+                match outcome {
+                    RoundOutcome::PlayerLost | RoundOutcome::InvalidSpell => continue,
+                    RoundOutcome::PlayerWon { spell_cast: false } => {
+                        // Enemies defeated by effects before any spell is cast in the current state
+                        return Some((mana_spent, spell_sequence(&states, index)));
+                    }
+                    RoundOutcome::PlayerWon { spell_cast: true } => {
+                        winning_states.insert(states.len());
+                    }
+                    RoundOutcome::Ongoing => {
+                        // Skip states already reached with the same or less mana spent
+                        let key = state.without_mana_spent();
+                        match min_mana_spent.get(&key) {
+                            Some(&min_mana) if min_mana <= mana => continue,
+                            _ => _ = min_mana_spent.insert(key, mana),
+                        }
+                    }
+                }
+                queue.push(Reverse((mana, states.len())));
+                states.push((state, Some((index, spell, target))));
+            }
         }
-        // // Check if the enemy is dead
-        if enemy.is_dead() {
-            update_min_mana(min_mana, &player);
-            return;
-        }
-        // Enemy turn
-        // // Process player effects and check if enemy is dead
-        player.process_effects(&mut enemy);
-        if enemy.is_dead() {
-            update_min_mana(min_mana, &player);
-            return;
-        }
-        // // Enemy deals damage to the player and check if player is dead
-        player.deal_damage(enemy.damage(), false);
-        if player.is_dead() {
-            return;
-        }
-        // Go to the next set of turns
-        conduct_fight_recursive(&player, &enemy, hard_mode, min_mana);
+    }
+    None
+}
+
+/// Gets the indices of the enemies worth targeting with the spell. Spells that do not deal damage
+/// are only cast at the first enemy still alive, since the target makes no difference.
+fn spell_targets(state: &EncounterState, spell: &Spell) -> Vec<usize> {
+    let alive = (0..state.enemies().len()).filter(|&i| state.is_enemy_alive(i));
+    match spell.deals_damage() {
+        true => alive.collect(),
+        false => alive.take(1).collect(),
     }
 }
 
-/// Updates the minimum mana value if the total mana spent by the player is a lower value.
-fn update_min_mana(min_mana: &mut Option<i64>, player: &MagicEntity) {
-    if min_mana.is_none() || min_mana.unwrap() > player.total_mana_spent() {
-        *min_mana = Some(player.total_mana_spent());
+/// Parses the difficulty options given to the "log" tool, starting from the easy difficulty.
+fn parse_difficulty(options: &[&str], spellbook: &Spellbook) -> Difficulty {
+    let mut difficulty = Difficulty::easy();
+    for option in options {
+        let value = |value: &str| {
+            value
+                .parse::<i64>()
+                .expect("Invalid difficulty option value!")
+        };
+        difficulty = match option.split_once('=') {
+            None if *option == "hard" => difficulty.with_player_drain(1),
+            Some(("drain", drain)) => difficulty.with_player_drain(value(drain)),
+            Some(("boss_damage", percent)) => difficulty.with_boss_damage_percent(value(percent)),
+            Some(("mana_cap", mana)) => difficulty.with_mana_cap(value(mana)),
+            Some(("start", name)) => match spellbook.get(name) {
+                Some(spell) => difficulty.with_starting_effect(spell),
+                None => panic!("Unknown spell: {}", name),
+            },
+            _ => difficulty,
+        };
     }
+    difficulty
+}
+
+/// Prints the battle log of the fight won by the player with the minimum amount of mana, in the
+/// narrative style or JSON format.
+fn print_battle_log(input: &ProblemInput, difficulty: &Difficulty, json: bool) {
+    let (enemy, spellbook) = input;
+    let player = &MagicEntity::new(PLAYER_HEALTH, 0, 0, PLAYER_MANA);
+    let spells = match conduct_fight(player, enemy, spellbook, difficulty) {
+        Some((_min_mana, spells)) => spells,
+        None => return println!("Player was unable to defeat the enemy!"),
+    };
+    let log = simulate_fight(player, enemy, &spells, difficulty);
+    match json {
+        true => println!("{}", log.to_json()),
+        false => print!("{}", log.to_narrative()),
+    }
+}
+
+/// Gets the sequence of spells (with their targets) cast to reach the fight state at the given
+/// index, by following the links back to the starting state.
+fn spell_sequence(states: &[FightState], index: usize) -> Vec<(Spell, usize)> {
+    let mut casts: Vec<(Spell, usize)> = vec![];
+    let mut current = states[index].1;
+    while let Some((previous, spell, target)) = current {
+        casts.push((spell.clone(), target));
+        current = states[previous].1;
+    }
+    casts.reverse();
+    casts
 }
 
 #[cfg(test)]
 mod test {
+    use aoc2015::utils::bespoke::{
+        simulate_encounter, Combatant, Enemy, EnemyAction, EnemyAi, FightEvent, ScriptCondition,
+    };
+
     use super::*;
 
     /// Tests the Day 22 Part 1 solver method against the actual problem solution.
@@ -176,4 +286,328 @@ mod test {
         let solution = solve_part2(&input);
         assert_eq!(1937, solution);
     }
+
+    /// Tests that the spell sequences returned by the search defeat the enemy for the mana cost
+    /// given, in both easy and HARD mode.
+    #[test]
+    fn test_day22_spell_sequence_actual() {
+        let (enemy, spellbook) = process_input_file(PROBLEM_INPUT_FILE);
+        for (difficulty, expected_mana) in [(Difficulty::easy(), 1824), (Difficulty::hard(), 1937)]
+        {
+            let player = MagicEntity::new(PLAYER_HEALTH, 0, 0, PLAYER_MANA);
+            let (min_mana, spells) =
+                conduct_fight(&player, &enemy, &spellbook, &difficulty).unwrap();
+            assert_eq!(expected_mana, min_mana);
+            let total: i64 = spells.iter().map(|spell| spell.cost()).sum();
+            assert_eq!(min_mana, total);
+            let encounter = Encounter::single(&enemy);
+            let mut state = encounter.start(&player, &difficulty);
+            let outcomes: Vec<RoundOutcome> = spells
+                .iter()
+                .map(|spell| play_round(&encounter, &mut state, spell, 0, &difficulty, None))
+                .collect();
+            let (last, rest) = outcomes.split_last().unwrap();
+            assert!(rest.iter().all(|outcome| *outcome == RoundOutcome::Ongoing));
+            assert_eq!(RoundOutcome::PlayerWon { spell_cast: true }, *last);
+            let log = simulate_fight(&player, &enemy, &spells, &difficulty);
+            assert_eq!(Some(Combatant::Player), log.winner());
+        }
+    }
+
+    /// Tests the search against the first example fight given in the problem description.
+    #[test]
+    fn test_day22_example_fight() {
+        let spellbook = load_spellbook(SPELLBOOK_FILE);
+        let player = MagicEntity::new(10, 0, 0, 250);
+        let enemy = MagicEntity::new(13, 8, 0, 0);
+        let (min_mana, spells) =
+            conduct_fight(&player, &enemy, &spellbook, &Difficulty::easy()).unwrap();
+        let names: Vec<&str> = spells.iter().map(|spell| spell.name()).collect();
+        assert_eq!(226, min_mana);
+        assert_eq!(vec!["Poison", "Magic Missile"], names);
+    }
+
+    /// Tests a spell defined only in the spellbook, along with rejection of invalid spellbooks.
+    #[test]
+    fn test_day22_custom_spellbook() {
+        let raw_spellbook = r#"[
+            {"name": "Smite", "cost": 10, "instant": {"damage": 3}},
+            {"name": "Regrowth", "cost": 20, "instant": {"heal": 1},
+             "per_turn": {"heal": 2}, "armour": 1, "duration": 2}
+        ]"#;
+        let spellbook = Spellbook::from_json(raw_spellbook).unwrap();
+        let player = MagicEntity::new(6, 0, 0, 100);
+        let enemy = MagicEntity::new(9, 3, 0, 0);
+        let (min_mana, spells) =
+            conduct_fight(&player, &enemy, &spellbook, &Difficulty::easy()).unwrap();
+        let names: Vec<&str> = spells.iter().map(|spell| spell.name()).collect();
+        // Player dies after two Smites without the healing and armour given by Regrowth
+        assert_eq!(50, min_mana);
+        assert_eq!(3, names.iter().filter(|&&name| name == "Smite").count());
+        assert!(names.contains(&"Regrowth"));
+        let duplicate = r#"[{"name": "Smite", "cost": 1}, {"name": "Smite", "cost": 2}]"#;
+        assert!(Spellbook::from_json(duplicate).is_err());
+        let no_duration = r#"[{"name": "Smite", "cost": 1, "per_turn": {"damage": 1}}]"#;
+        assert!(Spellbook::from_json(no_duration).is_err());
+    }
+
+    /// Tests the battle log narrative against the first example fight given in the problem
+    /// description.
+    #[test]
+    fn test_day22_battle_log_narrative() {
+        let spellbook = load_spellbook(SPELLBOOK_FILE);
+        let player = MagicEntity::new(10, 0, 0, 250);
+        let boss = MagicEntity::new(13, 8, 0, 0);
+        let spells = [
+            spellbook.get("Poison").unwrap().clone(),
+            spellbook.get("Magic Missile").unwrap().clone(),
+        ];
+        let log = simulate_fight(&player, &boss, &spells, &Difficulty::easy());
+        let expected = "\
+-- Player turn --
+- Player has 10 hit points, 0 armor, 250 mana
+- Boss has 13 hit points
+Player casts Poison.
+
+-- Boss turn --
+- Player has 10 hit points, 0 armor, 77 mana
+- Boss has 13 hit points
+Poison deals 3 damage; its timer is now 5.
+Boss attacks for 8 damage.
+
+-- Player turn --
+- Player has 2 hit points, 0 armor, 77 mana
+- Boss has 10 hit points
+Poison deals 3 damage; its timer is now 4.
+Player casts Magic Missile, dealing 4 damage.
+
+-- Boss turn --
+- Player has 2 hit points, 0 armor, 24 mana
+- Boss has 3 hit points
+Poison deals 3 damage; its timer is now 3.
+This kills the boss, and the player wins.
+";
+        assert_eq!(expected, log.to_narrative());
+        assert_eq!(Some(Combatant::Player), log.winner());
+    }
+
+    /// Tests the battle log JSON output, including the targets of effect ticks, and the player
+    /// losing when a spell cannot be cast.
+    #[test]
+    fn test_day22_battle_log_json() {
+        let spellbook = load_spellbook(SPELLBOOK_FILE);
+        let player = MagicEntity::new(10, 0, 0, 250);
+        let boss = MagicEntity::new(14, 8, 0, 0);
+        let shield = spellbook.get("Shield").unwrap().clone();
+        let log = simulate_fight(
+            &player,
+            &boss,
+            &[shield.clone(), shield],
+            &Difficulty::easy(),
+        );
+        assert_eq!(Some(Combatant::Boss), log.winner());
+        let json: serde_json::Value = serde_json::from_str(&log.to_json()).unwrap();
+        assert_eq!("boss", json["winner"]);
+        let events = json["events"].as_array().unwrap();
+        assert_eq!("enemy_status", events[1]["event"]);
+        assert_eq!(14, events[1]["health"]);
+        assert_eq!("spell_cast", events[2]["event"]);
+        assert_eq!(7, events[2]["armour"]);
+        assert_eq!("enemy_attack", events[6]["event"]);
+        assert_eq!("Boss", events[6]["enemy"]);
+        assert_eq!(1, events[6]["dealt"]);
+        assert_eq!("cannot_cast", events[10]["event"]);
+        assert_eq!("Shield", events[10]["spell"]);
+        // Effect ticks only name a target for effects aimed at an enemy
+        let player = MagicEntity::new(50, 0, 0, 250);
+        let recharge = spellbook.get("Recharge").unwrap().clone();
+        let poison = spellbook.get("Poison").unwrap().clone();
+        let log = simulate_fight(&player, &boss, &[recharge, poison], &Difficulty::easy());
+        let json: serde_json::Value = serde_json::from_str(&log.to_json()).unwrap();
+        let events = json["events"].as_array().unwrap();
+        let first_tick = |spell: &str| {
+            events
+                .iter()
+                .find(|event| event["event"] == "effect_tick" && event["spell"] == spell)
+                .unwrap()
+        };
+        let recharge_tick = first_tick("Recharge");
+        assert_eq!(101, recharge_tick["mana"]);
+        assert_eq!(None, recharge_tick.get("target"));
+        let poison_tick = first_tick("Poison");
+        assert_eq!(3, poison_tick["damage"]);
+        assert_eq!("Boss", poison_tick["target"]);
+    }
+
+    /// Tests that the search and the simulator both honour the difficulty modifiers.
+    #[test]
+    fn test_day22_difficulty_modifiers() {
+        let spellbook = load_spellbook(SPELLBOOK_FILE);
+        let player = MagicEntity::new(10, 0, 0, 250);
+        let boss = MagicEntity::new(13, 8, 0, 0);
+        // Poison then Magic Missile no longer wins when the boss hits twice as hard
+        let difficulty = Difficulty::easy().with_boss_damage_percent(200);
+        assert_eq!(None, conduct_fight(&player, &boss, &spellbook, &difficulty));
+        // Starting with Shield active absorbs most of the boss damage
+        let shield = spellbook.get("Shield").unwrap();
+        let difficulty = difficulty.with_starting_effect(shield);
+        let (min_mana, spells) = conduct_fight(&player, &boss, &spellbook, &difficulty).unwrap();
+        let log = simulate_fight(&player, &boss, &spells, &difficulty);
+        assert_eq!(Some(Combatant::Player), log.winner());
+        assert_eq!(FightEvent::EffectStarted { spell_id: 2 }, log.events()[0]);
+        assert_eq!(226, min_mana);
+        // Mana cap limits the mana restored by Recharge
+        let (enemy, spellbook) = process_input_file(PROBLEM_INPUT_FILE);
+        let player = MagicEntity::new(PLAYER_HEALTH, 0, 0, PLAYER_MANA);
+        let difficulty = Difficulty::easy().with_mana_cap(PLAYER_MANA);
+        let (min_mana, spells) = conduct_fight(&player, &enemy, &spellbook, &difficulty).unwrap();
+        // Cheapest win never restores mana beyond the cap, so the minimum is unchanged
+        assert_eq!(1824, min_mana);
+        let log = simulate_fight(&player, &enemy, &spells, &difficulty);
+        assert_eq!(Some(Combatant::Player), log.winner());
+        assert!(log.events().iter().all(|event| match event {
+            FightEvent::TurnStart { player_mana, .. } => *player_mana <= PLAYER_MANA,
+            _ => true,
+        }));
+    }
+
+    /// Tests that fight states reached by different spell orders compare equal once the mana spent
+    /// is ignored, and that spellbooks with more lasting effects than effect slots are rejected.
+    #[test]
+    fn test_day22_fight_state_slots() {
+        let spellbook = load_spellbook(SPELLBOOK_FILE);
+        let shield = spellbook.get("Shield").unwrap();
+        let poison = spellbook.get("Poison").unwrap();
+        let mut first = MagicEntity::new(PLAYER_HEALTH, 0, 0, PLAYER_MANA);
+        let mut second = first;
+        first.start_effect(shield);
+        first.start_effect(poison);
+        second.start_effect(shield);
+        second.start_effect(poison);
+        second.start_effect(shield);
+        assert_ne!(first, second);
+        // Restarting an effect moves it behind the other effects
+        second.start_effect(poison);
+        assert_eq!(first, second);
+        // Casting a spell only differs from starting its effect by the mana spent
+        let recharge = spellbook.get("Recharge").unwrap();
+        let mut enemy = MagicEntity::new(10, 0, 0, 0);
+        let mut cast = MagicEntity::new(PLAYER_HEALTH, 0, 0, PLAYER_MANA);
+        cast.cast_spell(recharge, &mut enemy, false).unwrap();
+        let mut started = MagicEntity::new(PLAYER_HEALTH, 0, 0, PLAYER_MANA - recharge.cost());
+        started.start_effect(recharge);
+        assert_ne!(cast, started);
+        assert_eq!(started, cast.without_mana_spent());
+        let effects: Vec<String> = (0..=MagicEntity::MAX_ACTIVE_EFFECTS)
+            .map(|i| format!(r#"{{"name": "Effect {}", "cost": 1, "duration": 1}}"#, i))
+            .collect();
+        let too_many = format!("[{}]", effects.join(","));
+        assert!(Spellbook::from_json(&too_many).is_err());
+        let effects = &effects[..MagicEntity::MAX_ACTIVE_EFFECTS];
+        assert!(Spellbook::from_json(&format!("[{}]", effects.join(","))).is_ok());
+    }
+
+    /// Tests an encounter with two enemies, where the player must choose the target of each spell.
+    #[test]
+    fn test_day22_multiple_enemies() {
+        let spellbook = load_spellbook(SPELLBOOK_FILE);
+        let player = MagicEntity::new(10, 0, 0, 250);
+        let encounter = Encounter::new(&[
+            Enemy::new("Goblin A", &MagicEntity::new(4, 3, 0, 0)),
+            Enemy::new("Goblin B", &MagicEntity::new(4, 3, 0, 0)),
+        ])
+        .unwrap();
+        let difficulty = Difficulty::easy();
+        let (min_mana, casts) =
+            conduct_encounter(&player, &encounter, &spellbook, &difficulty).unwrap();
+        // One Magic Missile at each goblin
+        assert_eq!(106, min_mana);
+        let targets: Vec<usize> = casts.iter().map(|(_, target)| *target).collect();
+        assert_eq!(vec![0, 1], targets);
+        let log = simulate_encounter(&player, &encounter, &casts, &difficulty);
+        assert_eq!(Some(Combatant::Player), log.winner());
+        let narrative = log.to_narrative();
+        assert!(narrative.contains("Player casts Magic Missile at Goblin A, dealing 4 damage."));
+        assert!(narrative.contains("- Goblin A has 0 hit points\n- Goblin B has 4 hit points"));
+        assert!(narrative.contains("Goblin A is defeated."));
+        assert!(narrative.contains("Goblin B attacks for 3 damage."));
+        assert!(narrative.ends_with("All enemies are defeated, and the player wins.\n"));
+        assert!(Encounter::new(&[]).is_err());
+    }
+
+    /// Tests a boss that casts its own spells from a rotation or a script, with the search still
+    /// finding the minimum mana win.
+    #[test]
+    fn test_day22_boss_abilities() {
+        let spellbook = load_spellbook(SPELLBOOK_FILE);
+        let boss_spellbook = Spellbook::from_json(
+            r#"[
+                {"name": "Barrier", "cost": 10, "armour": 7, "duration": 3},
+                {"name": "Mend", "cost": 20, "instant": {"heal": 6}}
+            ]"#,
+        )
+        .unwrap();
+        let barrier = boss_spellbook.get("Barrier").unwrap().clone();
+        let mend = boss_spellbook.get("Mend").unwrap().clone();
+        let player = MagicEntity::new(10, 0, 0, 250);
+        let boss = MagicEntity::new(13, 8, 0, 30);
+        let difficulty = Difficulty::easy();
+        // Rotation alternates between raising a barrier and attacking
+        let rotation = EnemyAi::Rotation(vec![
+            EnemyAction::Cast(barrier.clone()),
+            EnemyAction::Attack,
+        ]);
+        let enemy = Enemy::new("Boss", &boss).with_ai(rotation).unwrap();
+        let encounter = Encounter::new(&[enemy]).unwrap();
+        let (min_mana, casts) =
+            conduct_encounter(&player, &encounter, &spellbook, &difficulty).unwrap();
+        let log = simulate_encounter(&player, &encounter, &casts, &difficulty);
+        assert_eq!(Some(Combatant::Player), log.winner());
+        assert_eq!(
+            min_mana,
+            casts.iter().map(|(spell, _)| spell.cost()).sum::<i64>()
+        );
+        assert!(log
+            .to_narrative()
+            .contains("Boss casts Barrier, increasing armor by 7."));
+        // Script heals the boss when its health is low, otherwise it attacks
+        let script = EnemyAi::Script(vec![(
+            ScriptCondition::HealthBelow(12),
+            EnemyAction::Cast(mend.clone()),
+        )]);
+        let enemy = Enemy::new("Boss", &boss).with_ai(script).unwrap();
+        let encounter = Encounter::new(&[enemy]).unwrap();
+        let (healing_mana, casts) =
+            conduct_encounter(&player, &encounter, &spellbook, &difficulty).unwrap();
+        let log = simulate_encounter(&player, &encounter, &casts, &difficulty);
+        assert_eq!(Some(Combatant::Player), log.winner());
+        assert!(log
+            .events()
+            .iter()
+            .any(|event| matches!(event, FightEvent::EnemyCast { heal: 6, .. })));
+        // Healing boss costs more to defeat than the boss from the problem description
+        assert_eq!(568, healing_mana);
+        let cast_names: Vec<(&str, usize)> = casts
+            .iter()
+            .map(|(spell, target)| (spell.name(), *target))
+            .collect();
+        assert_eq!(
+            vec![
+                ("Recharge", 0),
+                ("Poison", 0),
+                ("Shield", 0),
+                ("Magic Missile", 0)
+            ],
+            cast_names
+        );
+        assert!(Enemy::new("Boss", &boss)
+            .with_ai(EnemyAi::Rotation(vec![]))
+            .is_err());
+        let other = Spellbook::from_json(r#"[{"name": "Other", "cost": 1}]"#).unwrap();
+        let mixed = EnemyAi::Rotation(vec![
+            EnemyAction::Cast(barrier),
+            EnemyAction::Cast(other.spells()[0].clone()),
+        ]);
+        assert!(Enemy::new("Boss", &boss).with_ai(mixed).is_err());
+    }
 }
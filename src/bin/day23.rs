@@ -1,15 +1,46 @@
+use std::env;
 use std::fs;
+use std::io::{self, Write};
 use std::time::Instant;
 
-use aoc2015::utils::bespoke::SimpleComputer;
+use aoc2015::utils::bespoke::{ExecutionTrace, MachineState, SimpleComputer, StopReason};
 
 const PROBLEM_NAME: &str = "Opening the Turing Lock";
 const PROBLEM_INPUT_FILE: &str = "./input/day23.txt";
 const PROBLEM_DAY: u64 = 23;
+const REGISTER_NAMES: [&str; 2] = ["a", "b"];
 
 /// Processes the AOC 2015 Day 23 input file and solves both parts of the problem. Solutions are
 /// printed to stdout.
+///
+/// The following tools can be run instead by giving their name as the first argument:
+/// - "debug [program_file]" starts the interactive debugger.
+/// - "disasm [program_file]" prints the disassembled program with resolved jump targets.
+/// - "trace <register_a> <trace_file> [program_file]" records the execution trace of the program,
+///   in CSV format if the trace file has the ".csv" extension or JSON lines format otherwise.
+/// - "diff <trace_file> <trace_file>" reports the first divergence between two execution traces.
+/// - "steps [program_file]" reports the number of steps taken with and without the loop optimiser.
+///
+/// The program in the problem input file is used if no program file is given.
 pub fn main() {
+    let args: Vec<String> = env::args().collect();
+    let program_file = |i: usize| args.get(i).map_or(PROBLEM_INPUT_FILE, |arg| arg.as_str());
+    match args.get(1).map(|arg| arg.as_str()) {
+        Some("debug") => return run_debugger(process_input_file(program_file(2))),
+        Some("disasm") => return print!("{}", process_input_file(program_file(2)).disassemble()),
+        Some("trace") if args.len() >= 4 => {
+            let register_a = args[2].parse::<isize>().expect("Invalid register a value!");
+            let trace = record_trace(&process_input_file(program_file(4)), register_a);
+            return write_trace_file(&args[3], &trace);
+        }
+        Some("diff") if args.len() >= 4 => {
+            let left = read_trace_file(&args[2]);
+            let right = read_trace_file(&args[3]);
+            return print_trace_divergence(&left, &right);
+        }
+        Some("steps") => return print_step_counts(&process_input_file(program_file(2))),
+        _ => (),
+    }
     let start = Instant::now();
     // Input processing
     let input = process_input_file(PROBLEM_INPUT_FILE);
@@ -47,31 +78,236 @@ fn process_input_file(filename: &str) -> SimpleComputer {
     // Read contents of problem input file
     let raw_input = fs::read_to_string(filename).unwrap();
     // Process input file contents into data structure
-    if let Some(simple_computer) = SimpleComputer::new(&raw_input, 0, 0) {
-        return simple_computer;
+    match SimpleComputer::new(&raw_input, &REGISTER_NAMES) {
+        Ok(simple_computer) => simple_computer,
+        Err(e) => panic!("Invalid input file format! {}", e),
     }
-    panic!("Invalid input file format!");
 }
 
 /// Solves AOC 2015 Day 23 Part 1 // Returns the value held in register 'b' of the computer after
 /// executing the stored instructions.
 fn solve_part1(computer: &SimpleComputer) -> isize {
     let mut computer = computer.clone();
-    computer.execute();
-    computer.register_b()
+    computer.execute_optimised();
+    computer.register("b").unwrap()
 }
 
 /// Solves AOC 2015 Day 23 Part 2 // Returns the value held in register 'b' of the computer after
 /// starting with register 'a' value of 1 and executing the stored instructions.
 fn solve_part2(computer: &SimpleComputer) -> isize {
     let mut computer = computer.clone();
-    computer.set_register_a(1);
+    computer.set_register("a", 1).unwrap();
+    computer.execute_optimised();
+    computer.register("b").unwrap()
+}
+
+/// Executes the program stored in the simple computer with the given starting value of register
+/// "a", and returns the execution trace recorded.
+fn record_trace(computer: &SimpleComputer, register_a: isize) -> ExecutionTrace {
+    let mut computer = computer.clone();
+    computer.set_register("a", register_a).unwrap();
+    computer.enable_trace();
     computer.execute();
-    computer.register_b()
+    computer.take_trace().unwrap()
+}
+
+/// Prints the number of steps taken to execute the program stored in the simple computer, with and
+/// without the loop optimiser, for both starting values of register "a" used in the problem.
+fn print_step_counts(computer: &SimpleComputer) {
+    for register_a in [0, 1] {
+        let mut interpreted = computer.clone();
+        interpreted.set_register("a", register_a).unwrap();
+        let mut optimised = interpreted.clone();
+        interpreted.execute();
+        let dispatched = optimised.execute_optimised();
+        assert_eq!(interpreted.registers(), optimised.registers());
+        println!(
+            "a = {}: {} steps interpreted, {} steps optimised",
+            register_a,
+            interpreted.steps(),
+            dispatched
+        );
+    }
+}
+
+/// Writes the execution trace to the given file, in CSV format if the file has the ".csv"
+/// extension or JSON lines format otherwise.
+fn write_trace_file(filename: &str, trace: &ExecutionTrace) {
+    let output = match filename.ends_with(".csv") {
+        true => trace.to_csv(),
+        false => trace.to_json_lines(),
+    };
+    fs::write(filename, output).unwrap();
+    println!(
+        "Wrote {} trace entries to {}",
+        trace.entries().len(),
+        filename
+    );
+}
+
+/// Reads the execution trace from the given file, in CSV format if the file has the ".csv"
+/// extension or JSON lines format otherwise.
+fn read_trace_file(filename: &str) -> ExecutionTrace {
+    let raw_input = fs::read_to_string(filename).unwrap();
+    let trace = match filename.ends_with(".csv") {
+        true => ExecutionTrace::from_csv(&raw_input),
+        false => ExecutionTrace::from_json_lines(&raw_input),
+    };
+    match trace {
+        Some(trace) => trace,
+        None => panic!("Invalid trace file format: {}", filename),
+    }
+}
+
+/// Prints the first divergence between the two execution traces.
+fn print_trace_divergence(left: &ExecutionTrace, right: &ExecutionTrace) {
+    let Some(divergence) = left.first_divergence(right) else {
+        println!("Traces are identical ({} entries).", left.entries().len());
+        return;
+    };
+    let describe = |entry: Option<String>| entry.unwrap_or_else(|| String::from("<end of trace>"));
+    println!("Traces diverge at entry {}:", divergence.index());
+    if left.register_names() != right.register_names() {
+        println!(
+            "[!] Register names differ: {:?} vs {:?}",
+            left.register_names(),
+            right.register_names()
+        );
+    }
+    println!(
+        "[<] {}",
+        describe(divergence.left().map(|entry| entry.to_string()))
+    );
+    println!(
+        "[>] {}",
+        describe(divergence.right().map(|entry| entry.to_string()))
+    );
+}
+
+/// Runs the interactive debugger for the simple computer, reading commands from stdin until the
+/// "quit" command is given or stdin is closed.
+fn run_debugger(mut computer: SimpleComputer) {
+    println!(
+        "Commands: step [n], continue, until <pc>, break <pc>, delete <pc>, watch <register>,"
+    );
+    println!("          unwatch <register>, jump <pc>, save <file>, load <file>, regs, quit");
+    print_debugger_state(&computer);
+    loop {
+        print!("(debug) ");
+        io::stdout().flush().unwrap();
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line).unwrap() == 0 {
+            return;
+        }
+        let mut tokens = line.split_whitespace();
+        let command = tokens.next();
+        let arg = tokens.next();
+        let pc_arg = arg.and_then(|arg| arg.parse::<isize>().ok());
+        match (command, arg) {
+            (None, _) => (),
+            (Some("step" | "s"), _) => match arg.map_or(Ok(1), |arg| arg.parse::<u64>()) {
+                Ok(count) => {
+                    for _ in 0..count {
+                        if !computer.step() {
+                            break;
+                        }
+                    }
+                    print_debugger_state(&computer);
+                }
+                Err(_) => println!("Invalid step count!"),
+            },
+            (Some("continue" | "c"), None) => {
+                print_stop_reason(computer.run());
+                print_debugger_state(&computer);
+            }
+            (Some("until" | "u"), Some(_)) if pc_arg.is_some() => {
+                print_stop_reason(computer.run_until(pc_arg.unwrap()));
+                print_debugger_state(&computer);
+            }
+            (Some("break" | "b"), Some(_)) if pc_arg.is_some() => {
+                computer.add_breakpoint(pc_arg.unwrap());
+            }
+            (Some("delete" | "d"), Some(_)) if pc_arg.is_some() => {
+                computer.remove_breakpoint(pc_arg.unwrap());
+            }
+            (Some("watch" | "w"), Some(register)) => {
+                if let Err(e) = computer.add_watchpoint(register) {
+                    println!("{}", e);
+                }
+            }
+            (Some("unwatch"), Some(register)) => computer.remove_watchpoint(register),
+            (Some("jump" | "j"), Some(_)) if pc_arg.is_some() => {
+                computer.set_pc(pc_arg.unwrap());
+                print_debugger_state(&computer);
+            }
+            (Some("save"), Some(filename)) => {
+                let snapshot = serde_json::to_string_pretty(&computer.snapshot()).unwrap();
+                match fs::write(filename, snapshot) {
+                    Ok(()) => println!("Saved machine state to {}.", filename),
+                    Err(e) => println!("Failed to save machine state! {}", e),
+                }
+            }
+            (Some("load"), Some(filename)) => match read_snapshot_file(filename) {
+                Ok(snapshot) => match computer.restore(&snapshot) {
+                    Ok(()) => print_debugger_state(&computer),
+                    Err(e) => println!("{}", e),
+                },
+                Err(e) => println!("Failed to load machine state! {}", e),
+            },
+            (Some("regs" | "r"), None) => print_debugger_state(&computer),
+            (Some("quit" | "q"), None) => return,
+            _ => println!("Invalid command: {}", line.trim()),
+        }
+    }
+}
+
+/// Reads the snapshot of a simple computer's machine state from the given JSON file.
+fn read_snapshot_file(filename: &str) -> Result<MachineState, String> {
+    let raw_input = fs::read_to_string(filename).map_err(|e| e.to_string())?;
+    serde_json::from_str(&raw_input).map_err(|e| e.to_string())
+}
+
+/// Prints the reason that the simple computer paused execution in the debugger.
+fn print_stop_reason(reason: StopReason) {
+    match reason {
+        StopReason::Halted => println!("Halted."),
+        StopReason::Breakpoint { pc } => println!("Breakpoint reached at pc {}.", pc),
+        StopReason::Watchpoint {
+            register,
+            old_value,
+            new_value,
+        } => println!(
+            "Register {} changed from {} to {}.",
+            register, old_value, new_value
+        ),
+    }
+}
+
+/// Prints the program counter, registers, instruction count and next instruction of the simple
+/// computer in the debugger.
+fn print_debugger_state(computer: &SimpleComputer) {
+    let next = computer
+        .current_instruction()
+        .unwrap_or_else(|| String::from("<halted>"));
+    let registers: Vec<String> = computer
+        .register_names()
+        .iter()
+        .zip(computer.registers())
+        .map(|(name, value)| format!("{}: {}", name, value))
+        .collect();
+    println!(
+        "pc: {} | {} | steps: {} | next: {}",
+        computer.pc(),
+        registers.join(" | "),
+        computer.steps(),
+        next
+    );
 }
 
 #[cfg(test)]
 mod test {
+    use aoc2015::utils::bespoke::{AssemblyErrorKind, ExecutionOutcome};
+
     use super::*;
 
     /// Tests the Day 23 Part 1 solver method against the actual problem solution.
@@ -89,4 +325,311 @@ mod test {
         let solution = solve_part2(&input);
         assert_eq!(160, solution);
     }
+
+    /// Tests the debugger methods of the simple computer against the actual problem input.
+    #[test]
+    fn test_day23_debugger_actual() {
+        let mut computer = process_input_file(PROBLEM_INPUT_FILE);
+        // Pause at the first change to register "b", made by the "inc b" instruction at pc 40
+        computer.add_watchpoint("b").unwrap();
+        let expected = StopReason::Watchpoint {
+            register: String::from("b"),
+            old_value: 0,
+            new_value: 1,
+        };
+        assert_eq!(expected, computer.run());
+        assert_eq!(41, computer.pc());
+        computer.remove_watchpoint("b");
+        // Resume until the start of the loop
+        assert_eq!(StopReason::Breakpoint { pc: 39 }, computer.run_until(39));
+        assert_eq!(
+            Some(String::from("jio a, +8")),
+            computer.current_instruction()
+        );
+        let steps = computer.steps();
+        assert!(computer.step());
+        assert_eq!(steps + 1, computer.steps());
+        // Run to completion
+        assert_eq!(StopReason::Halted, computer.run());
+        assert!(computer.is_halted());
+        assert_eq!(Some(307), computer.register("b"));
+    }
+
+    /// Tests that execution traces survive the round trip through both file formats, and that
+    /// the traces for the Part 1 and Part 2 starting values diverge at the first instruction.
+    #[test]
+    fn test_day23_trace_actual() {
+        let input = process_input_file(PROBLEM_INPUT_FILE);
+        let trace_part1 = record_trace(&input, 0);
+        let trace_part2 = record_trace(&input, 1);
+        let last_entry = trace_part1.entries().last().unwrap();
+        assert_eq!(&[1, 307], last_entry.registers_after());
+        assert_eq!(
+            Some(trace_part1.clone()),
+            ExecutionTrace::from_json_lines(&trace_part1.to_json_lines())
+        );
+        assert_eq!(
+            Some(trace_part1.clone()),
+            ExecutionTrace::from_csv(&trace_part1.to_csv())
+        );
+        assert_eq!(None, trace_part1.first_divergence(&trace_part1));
+        let divergence = trace_part1.first_divergence(&trace_part2).unwrap();
+        assert_eq!(0, divergence.index());
+        assert_eq!(&[0, 0], divergence.left().unwrap().registers_before());
+        assert_eq!(&[1, 0], divergence.right().unwrap().registers_before());
+    }
+
+    /// Tests that a trace over registers not in sorted order survives the round trip through both
+    /// file formats, and that traces with the same values over reordered registers diverge.
+    #[test]
+    fn test_day23_trace_register_order() {
+        let computer = SimpleComputer::new("inc a\ninc b\ninc b", &["b", "a"]).unwrap();
+        let trace = record_trace(&computer, 3);
+        assert_eq!(
+            &[String::from("b"), String::from("a")],
+            trace.register_names()
+        );
+        assert_eq!(&[2, 4], trace.entries().last().unwrap().registers_after());
+        // Register names are written once, in order, in the header line of the JSON lines trace
+        let json_lines = trace.to_json_lines();
+        assert_eq!(
+            Some(r#"{"registers":["b","a"]}"#),
+            json_lines.lines().next()
+        );
+        assert_eq!(trace.entries().len() + 1, json_lines.lines().count());
+        let from_json = ExecutionTrace::from_json_lines(&json_lines).unwrap();
+        let from_csv = ExecutionTrace::from_csv(&trace.to_csv()).unwrap();
+        assert_eq!(trace, from_json);
+        assert_eq!(trace, from_csv);
+        assert_eq!(None, trace.first_divergence(&from_json));
+        assert_eq!(None, from_json.first_divergence(&from_csv));
+        // Same instructions and values, but with the registers listed the other way round
+        let reordered = SimpleComputer::new("inc a\ninc b\ninc b", &["a", "b"]).unwrap();
+        let divergence = trace
+            .first_divergence(&record_trace(&reordered, 3))
+            .unwrap();
+        assert_eq!(0, divergence.index());
+        assert!(divergence.left().is_some() && divergence.right().is_some());
+    }
+
+    /// Tests that a program using labels, comments, blank lines and free-form operand layout
+    /// assembles into the same instructions as the equivalent program using offsets.
+    #[test]
+    fn test_day23_assembler_labels() {
+        let with_labels = SimpleComputer::new(
+            "# Counts the Collatz steps for register a\n\
+             \n\
+             loop: JIO a, done   ; stop once a reaches 1\n\
+             \x20   inc b\n\
+             \x20   jie a,halve\n\
+             \x20   tpl a\n\
+             \x20   Inc   a\n\
+             \x20   jmp loop\n\
+             halve:\n\
+             \x20   hlf a\n\
+             \x20   jmp loop\n\
+             done:",
+            &REGISTER_NAMES,
+        )
+        .unwrap();
+        let with_offsets = SimpleComputer::new(
+            "jio a, +8\ninc b\njie a, +4\ntpl a\ninc a\njmp -5\nhlf a\njmp -7",
+            &REGISTER_NAMES,
+        )
+        .unwrap();
+        assert_eq!(with_offsets.disassemble(), with_labels.disassemble());
+        assert!(with_labels
+            .disassemble()
+            .starts_with("0000  jio a, +8    ; -> 0008\n0001  inc b\n"));
+        let mut computer = with_labels;
+        computer.set_register("a", 7).unwrap();
+        computer.execute();
+        assert_eq!(Some(16), computer.register("b"));
+    }
+
+    /// Tests that assembly errors report the line number and reason for the error.
+    #[test]
+    fn test_day23_assembler_errors() {
+        let cases = [
+            (
+                "inc a\n\ninc c",
+                3,
+                AssemblyErrorKind::InvalidRegister(String::from("c")),
+            ),
+            (
+                "foo a",
+                1,
+                AssemblyErrorKind::UnknownMnemonic(String::from("foo")),
+            ),
+            ("jio a", 1, AssemblyErrorKind::MissingOperand),
+            (
+                "hlf a b",
+                1,
+                AssemblyErrorKind::UnexpectedOperand(String::from("b")),
+            ),
+            (
+                "x:\nx: inc a",
+                2,
+                AssemblyErrorKind::DuplicateLabel(String::from("x")),
+            ),
+            (
+                "inc a\njmp end",
+                2,
+                AssemblyErrorKind::UndefinedLabel(String::from("end")),
+            ),
+            ("inc a!", 1, AssemblyErrorKind::UnexpectedCharacter('!')),
+            (
+                "cpy 1 2",
+                1,
+                AssemblyErrorKind::InvalidRegister(String::from("2")),
+            ),
+            (
+                "inc a\ncpy - a",
+                2,
+                AssemblyErrorKind::InvalidNumber(String::from("-")),
+            ),
+            (
+                "inc +",
+                1,
+                AssemblyErrorKind::InvalidNumber(String::from("+")),
+            ),
+            (
+                "+ a",
+                1,
+                AssemblyErrorKind::InvalidNumber(String::from("+")),
+            ),
+            (
+                "jmp +",
+                1,
+                AssemblyErrorKind::InvalidTarget(String::from("+")),
+            ),
+            (
+                "jnz a -",
+                1,
+                AssemblyErrorKind::InvalidTarget(String::from("-")),
+            ),
+        ];
+        for (program, line_number, kind) in cases {
+            let error = SimpleComputer::new(program, &REGISTER_NAMES).err().unwrap();
+            assert_eq!(line_number, error.line_number());
+            assert_eq!(&kind, error.kind());
+        }
+    }
+
+    /// Tests the assembunny-style instructions using a computer with four registers.
+    #[test]
+    fn test_day23_extended_instructions() {
+        let program = "cpy 3 c\nloop: inc a\nout a\ndec c\njnz c loop\ncpy a d";
+        let mut computer = SimpleComputer::new(program, &["a", "b", "c", "d"]).unwrap();
+        computer.execute();
+        assert_eq!(&[3, 0, 0, 3], computer.registers());
+        assert_eq!(&[1, 2, 3], computer.output());
+        let error = SimpleComputer::new("cpy 1 e", &["a", "b", "c", "d"])
+            .err()
+            .unwrap();
+        assert_eq!(
+            &AssemblyErrorKind::InvalidRegister(String::from("e")),
+            error.kind()
+        );
+    }
+
+    /// Tests that executing with a step budget reports halting, reaching the step limit and
+    /// detecting an infinite loop.
+    #[test]
+    fn test_day23_execute_with_limit() {
+        let mut computer = process_input_file(PROBLEM_INPUT_FILE);
+        let outcome = computer.execute_with_limit(10_000);
+        assert!(matches!(outcome, ExecutionOutcome::Halted { .. }));
+        assert_eq!(&[1, 307], outcome.registers());
+        assert_eq!(computer.steps(), outcome.steps());
+        // Counter that keeps increasing never repeats a state
+        let mut computer = SimpleComputer::new("inc a\njmp -1", &REGISTER_NAMES).unwrap();
+        let expected = ExecutionOutcome::StepLimit {
+            steps: 100,
+            registers: vec![50, 0],
+        };
+        assert_eq!(expected, computer.execute_with_limit(100));
+        // Counter that wraps back to its starting value loops forever
+        let program = "inc a\njio a, +2\njmp -2\nhlf a\ntpl b\njmp -5";
+        let mut computer = SimpleComputer::new(program, &REGISTER_NAMES).unwrap();
+        let expected = ExecutionOutcome::LoopDetected {
+            steps: 5,
+            registers: vec![0, 0],
+            loop_start: 0,
+        };
+        assert_eq!(expected, computer.execute_with_limit(100));
+    }
+
+    /// Tests that the loop optimiser gives the same results and step counts as interpreting the
+    /// problem input program, while dispatching fewer instructions.
+    #[test]
+    fn test_day23_execute_optimised_actual() {
+        let computer = process_input_file(PROBLEM_INPUT_FILE);
+        for register_a in [0, 1] {
+            let mut interpreted = computer.clone();
+            interpreted.set_register("a", register_a).unwrap();
+            let mut optimised = interpreted.clone();
+            interpreted.execute();
+            let dispatched = optimised.execute_optimised();
+            assert_eq!(interpreted.registers(), optimised.registers());
+            assert_eq!(interpreted.steps(), optimised.steps());
+            assert!(dispatched < 100);
+        }
+    }
+
+    /// Tests the repeated increment and register addition idioms, and falling back to
+    /// interpretation when a jump enters the middle of the addition loop.
+    #[test]
+    fn test_day23_execute_optimised_idioms() {
+        let program = "cpy 5 b\ninc a\ninc a\ndec a\ninc a\ninc c\nloop: inc a\ndec b\njnz b loop";
+        let mut computer = SimpleComputer::new(program, &["a", "b", "c"]).unwrap();
+        assert_eq!(4, computer.execute_optimised());
+        assert_eq!(&[7, 0, 1], computer.registers());
+        assert_eq!(21, computer.steps());
+        let program = "cpy 3 b\njmp +2\nloop: inc a\ndec b\njnz b loop";
+        let mut interpreted = SimpleComputer::new(program, &["a", "b"]).unwrap();
+        let mut optimised = interpreted.clone();
+        interpreted.execute();
+        optimised.execute_optimised();
+        assert_eq!(&[2, 0], optimised.registers());
+        assert_eq!(interpreted.registers(), optimised.registers());
+        assert_eq!(interpreted.steps(), optimised.steps());
+    }
+
+    /// Tests that a snapshot taken part way through a run can be serialised, restored and resumed
+    /// to give the same result as an uninterrupted run.
+    #[test]
+    fn test_day23_snapshot_restore() {
+        let mut computer = process_input_file(PROBLEM_INPUT_FILE);
+        computer.set_register("a", 1).unwrap();
+        for _ in 0..100 {
+            computer.step();
+        }
+        let json = serde_json::to_string(&computer.snapshot()).unwrap();
+        let snapshot: MachineState = serde_json::from_str(&json).unwrap();
+        assert_eq!(computer.snapshot(), snapshot);
+        assert_eq!(100, snapshot.steps());
+        let mut resumed = SimpleComputer::new("", &REGISTER_NAMES).unwrap();
+        resumed.restore(&snapshot).unwrap();
+        assert_eq!(computer.disassemble(), resumed.disassemble());
+        computer.execute();
+        resumed.execute();
+        assert_eq!(&[1, 160], resumed.registers());
+        assert_eq!(computer.steps(), resumed.steps());
+        // Setting the program counter past the end of the program halts the computer
+        resumed.set_pc(-1);
+        assert!(resumed.is_halted());
+        // Invalid snapshot leaves the computer unchanged
+        let invalid = serde_json::json!({
+            "program": ["inc c"],
+            "pc": 0,
+            "register_names": ["a", "b"],
+            "registers": [0, 0],
+            "output": [],
+            "steps": 0,
+        });
+        let invalid: MachineState = serde_json::from_value(invalid).unwrap();
+        assert!(resumed.restore(&invalid).is_err());
+        assert_eq!(&[1, 160], resumed.registers());
+    }
 }
@@ -1,4 +1,3 @@
-use core::panic;
 use std::fs;
 use std::time::Instant;
 
@@ -56,9 +55,8 @@ fn process_input_file(filename: &str) -> Vec<u128> {
 /// compartment (the one with the fewest possible number of presents) where there is a total of
 /// three compartments.
 fn solve_part1(values: &[u128]) -> u128 {
-    let target: u128 = values.iter().sum::<u128>() / 3;
-    if let Some(qe) = find_compartment1_qe(values, target) {
-        return qe;
+    if let Some(partition) = find_optimal_partition(values, 3) {
+        return quantum_entanglement(&partition[0]);
     }
     panic!("Could not determine the compartment 1 QE value!");
 }
@@ -67,73 +65,155 @@ fn solve_part1(values: &[u128]) -> u128 {
 /// compartment (the one with the fewest possible number of presents) where these is a total of four
 /// compartments.
 fn solve_part2(values: &[u128]) -> u128 {
-    let target: u128 = values.iter().sum::<u128>() / 4;
-    if let Some(qe) = find_compartment1_qe(values, target) {
-        return qe;
+    if let Some(partition) = find_optimal_partition(values, 4) {
+        return quantum_entanglement(&partition[0]);
     }
     panic!("Could not determine the compartment 1 QE value!");
 }
 
-/// Finds the quantum entanglement value for the first compartment (the one with the fewest possible
-/// number of presents).
-fn find_compartment1_qe(values: &[u128], target: u128) -> Option<u128> {
-    let mut comp1_qe: Option<u128> = None;
-    let mut min_len: Option<usize> = None;
-    find_compartment1_qe_recursive(values, target, &[], 0, 0, &mut comp1_qe, &mut min_len);
-    comp1_qe
+/// Calculates the quantum entanglement value of the group of presents (product of the weights).
+fn quantum_entanglement(group: &[u128]) -> u128 {
+    group.iter().product()
 }
 
-/// Recursive helper function to find the quantum entanglement value for the first compartment (the
-/// one with the fewest possible number of presents).
-fn find_compartment1_qe_recursive(
-    values: &[u128],
+/// Finds the optimal partition of the presents into the given number of groups of equal weight.
+/// The first group has the fewest presents possible, then the lowest quantum entanglement value,
+/// and the remaining presents are proven to be partitionable into the other groups. Returned value
+/// is None if the presents cannot be partitioned. Presents are assumed to have positive weights.
+fn find_optimal_partition(values: &[u128], groups: usize) -> Option<Vec<Vec<u128>>> {
+    let total: u128 = values.iter().sum();
+    if groups == 0 || !total.is_multiple_of(groups as u128) {
+        return None;
+    }
+    // Heaviest presents are tried first, so groups reach the target weight with fewer presents
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable_by(|a, b| b.cmp(a));
+    let mut search = PartitionSearch {
+        values: &sorted,
+        target: total / groups as u128,
+        groups,
+        used: vec![false; sorted.len()],
+        best_qe: None,
+        best: None,
+    };
+    // Search first groups in order of size, stopping at the first size with a valid partition
+    let mut picked: Vec<usize> = vec![];
+    for size in 1..=sorted.len() {
+        search.find_first_group(size, 0, 0, 1, &mut picked);
+        if search.best.is_some() {
+            return search.best;
+        }
+    }
+    None
+}
+
+/// Represents the state of the search for the optimal partition of the presents, sorted by
+/// descending weight. Presents are referred to by their index in the sorted weights.
+struct PartitionSearch<'a> {
+    values: &'a [u128],
     target: u128,
-    picked: &[u128],
-    running_total: u128,
-    index: usize,
-    comp1_qe: &mut Option<u128>,
-    min_len: &mut Option<usize>,
-) {
-    // Check if the compartment total equals the target
-    if running_total == target {
-        if min_len.is_none() || min_len.unwrap() > picked.len() {
-            *min_len = Some(picked.len());
-            *comp1_qe = Some(picked.iter().product());
-        } else if min_len.unwrap() == picked.len() {
-            let qe: u128 = picked.iter().product();
-            if comp1_qe.is_none() || comp1_qe.unwrap() > qe {
-                *comp1_qe = Some(qe);
+    groups: usize,
+    used: Vec<bool>,
+    best_qe: Option<u128>,
+    best: Option<Vec<Vec<u128>>>,
+}
+
+impl PartitionSearch<'_> {
+    /// Searches for a first group with the given number of presents, extending the picked presents
+    /// with those from the start index onwards. Branches are pruned if they cannot reach the target
+    /// weight or cannot improve on the best QE value found so far. Each complete group is only
+    /// accepted if the remaining presents can be partitioned into the other groups.
+    fn find_first_group(
+        &mut self,
+        size: usize,
+        start: usize,
+        total: u128,
+        qe: u128,
+        picked: &mut Vec<usize>,
+    ) {
+        if picked.len() == size {
+            if total == self.target {
+                if let Some(mut partition) = self.partition_remainder(self.groups - 1) {
+                    partition.insert(0, self.group_values(picked));
+                    self.best_qe = Some(qe);
+                    self.best = Some(partition);
+                }
             }
+            return;
+        }
+        let slots = (size - picked.len()) as u128;
+        for i in start..self.values.len() {
+            let value = self.values[i];
+            // Remaining presents are no heavier, so cannot reach the target from here onwards
+            if total + value * slots < self.target {
+                break;
+            }
+            if total + value > self.target || self.best_qe.is_some_and(|best| qe * value >= best) {
+                continue;
+            }
+            self.used[i] = true;
+            picked.push(i);
+            self.find_first_group(size, i + 1, total + value, qe * value, picked);
+            picked.pop();
+            self.used[i] = false;
         }
-        return;
     }
-    // Check if the target or values space have been overshot
-    if running_total > target || index >= values.len() {
-        return;
+
+    /// Partitions the presents not yet used into the given number of groups of the target weight.
+    /// Returned value is None if the remaining presents cannot be partitioned.
+    fn partition_remainder(&mut self, groups: usize) -> Option<Vec<Vec<u128>>> {
+        let remaining: Vec<usize> = (0..self.values.len()).filter(|&i| !self.used[i]).collect();
+        match groups {
+            0 => Some(vec![]),
+            1 => Some(vec![self.group_values(&remaining)]),
+            // The first remaining present must be in one of the groups, so start the group with it
+            _ => {
+                let first = *remaining.first()?;
+                self.used[first] = true;
+                let mut picked = vec![first];
+                let partition = self.fill_group(first + 1, self.values[first], &mut picked, groups);
+                self.used[first] = false;
+                partition
+            }
+        }
+    }
+
+    /// Fills the current group of the remainder up to the target weight with presents from the
+    /// start index onwards, then partitions the presents left over into the other groups.
+    fn fill_group(
+        &mut self,
+        start: usize,
+        total: u128,
+        picked: &mut Vec<usize>,
+        groups: usize,
+    ) -> Option<Vec<Vec<u128>>> {
+        if total == self.target {
+            let mut partition = self.partition_remainder(groups - 1)?;
+            partition.insert(0, self.group_values(picked));
+            return Some(partition);
+        }
+        for i in start..self.values.len() {
+            if self.used[i] || total + self.values[i] > self.target {
+                continue;
+            }
+            self.used[i] = true;
+            picked.push(i);
+            let partition = self.fill_group(i + 1, total + self.values[i], picked, groups);
+            picked.pop();
+            self.used[i] = false;
+            if partition.is_some() {
+                return partition;
+            }
+        }
+        None
+    }
+
+    /// Gets the weights of the presents with the given indices, in ascending order.
+    fn group_values(&self, indices: &[usize]) -> Vec<u128> {
+        let mut group: Vec<u128> = indices.iter().map(|&i| self.values[i]).collect();
+        group.sort_unstable();
+        group
     }
-    // Select the value at the current index
-    let mut new_picked = picked.to_vec();
-    new_picked.push(values[index]);
-    // Select
-    find_compartment1_qe_recursive(
-        values,
-        target,
-        &new_picked,
-        running_total + values[index],
-        index + 1,
-        comp1_qe,
-        min_len,
-    );
-    // No select
-    find_compartment1_qe_recursive(
-        values,
-        target,
-        picked,
-        running_total,
-        index + 1,
-        comp1_qe,
-        min_len,
-    );
 }
 
 #[cfg(test)]
@@ -155,4 +235,45 @@ mod test {
         let solution = solve_part2(&input);
         assert_eq!(80393059, solution);
     }
+
+    /// Tests the partition solver against the example given in the problem description, checking
+    /// that every group of the partition has the same weight.
+    #[test]
+    fn test_day24_partition_example() {
+        let values: Vec<u128> = vec![1, 2, 3, 4, 5, 7, 8, 9, 10, 11];
+        for (groups, expected_first, expected_qe) in [(3, vec![9, 11], 99), (4, vec![4, 11], 44)] {
+            let partition = find_optimal_partition(&values, groups).unwrap();
+            assert_eq!(groups, partition.len());
+            assert_eq!(expected_first, partition[0]);
+            assert_eq!(expected_qe, quantum_entanglement(&partition[0]));
+            assert!(partition
+                .iter()
+                .all(|group| group.iter().sum::<u128>() == 20 * 3 / groups as u128));
+            let mut all: Vec<u128> = partition.concat();
+            all.sort_unstable();
+            assert_eq!(values, all);
+        }
+    }
+
+    /// Tests that first groups leaving presents that cannot be partitioned are rejected.
+    #[test]
+    fn test_day24_partition_remainder() {
+        // Lowest QE group of three presents (1, 12, 29) leaves 2, 4, 9, 15, 26, 28 which cannot be
+        // split into two groups of 42
+        let values: Vec<u128> = vec![1, 2, 4, 9, 12, 15, 26, 28, 29];
+        let partition = find_optimal_partition(&values, 3).unwrap();
+        assert_eq!(vec![1, 15, 26], partition[0]);
+        assert_eq!(390, quantum_entanglement(&partition[0]));
+        assert!(partition
+            .iter()
+            .all(|group| group.iter().sum::<u128>() == 42));
+        // Only group of weight 6 is 1 and 5, leaving three presents of weight 4
+        assert_eq!(None, find_optimal_partition(&[5, 1, 4, 4, 4], 3));
+        assert_eq!(None, find_optimal_partition(&[1, 2, 4], 2));
+        assert_eq!(None, find_optimal_partition(&[1, 2, 3], 0));
+        assert_eq!(
+            Some(vec![vec![1, 2, 3]]),
+            find_optimal_partition(&[1, 2, 3], 1)
+        );
+    }
 }
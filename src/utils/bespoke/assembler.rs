@@ -0,0 +1,410 @@
+use std::collections::HashMap;
+use std::fmt;
+
+/// Represents an instruction operand that is either the value held in a register (given by its
+/// index in the register file) or a specific value.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(super) enum Operand {
+    Register(usize),
+    Value(isize),
+}
+
+impl Operand {
+    /// Formats the operand using the given register names.
+    fn format(&self, register_names: &[String]) -> String {
+        match self {
+            Operand::Register(register) => register_names[*register].to_string(),
+            Operand::Value(value) => value.to_string(),
+        }
+    }
+}
+
+/// Represents the different instructions that can be executed by the simple computer. Registers
+/// are given by their index in the register file.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(super) enum Instruction {
+    Half { register: usize },                            // hlf
+    Triple { register: usize },                          // tpl
+    Increment { register: usize },                       // inc
+    Decrement { register: usize },                       // dec
+    Copy { source: Operand, register: usize },           // cpy
+    Jump { offset: isize },                              // jmp
+    JumpIfEven { register: usize, offset: isize },       // jie
+    JumpIfOne { register: usize, offset: isize },        // jio
+    JumpIfNotZero { condition: Operand, offset: isize }, // jnz
+    Output { source: Operand },                          // out
+}
+
+impl Instruction {
+    /// Gets the jump offset of the instruction, if it is a jump instruction.
+    fn offset(&self) -> Option<isize> {
+        match self {
+            Instruction::Jump { offset }
+            | Instruction::JumpIfEven { offset, .. }
+            | Instruction::JumpIfOne { offset, .. }
+            | Instruction::JumpIfNotZero { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+
+    /// Updates the jump offset of the instruction, if it is a jump instruction.
+    fn set_offset(&mut self, new_offset: isize) {
+        match self {
+            Instruction::Jump { offset }
+            | Instruction::JumpIfEven { offset, .. }
+            | Instruction::JumpIfOne { offset, .. }
+            | Instruction::JumpIfNotZero { offset, .. } => *offset = new_offset,
+            _ => (),
+        }
+    }
+
+    /// Formats the instruction in its input syntax, using the given register names.
+    pub(super) fn format(&self, register_names: &[String]) -> String {
+        let name = |register: &usize| &register_names[*register];
+        match self {
+            Instruction::Half { register } => format!("hlf {}", name(register)),
+            Instruction::Triple { register } => format!("tpl {}", name(register)),
+            Instruction::Increment { register } => format!("inc {}", name(register)),
+            Instruction::Decrement { register } => format!("dec {}", name(register)),
+            Instruction::Copy { source, register } => {
+                format!("cpy {} {}", source.format(register_names), name(register))
+            }
+            Instruction::Jump { offset } => format!("jmp {:+}", offset),
+            Instruction::JumpIfEven { register, offset } => {
+                format!("jie {}, {:+}", name(register), offset)
+            }
+            Instruction::JumpIfOne { register, offset } => {
+                format!("jio {}, {:+}", name(register), offset)
+            }
+            Instruction::JumpIfNotZero { condition, offset } => {
+                format!("jnz {} {:+}", condition.format(register_names), offset)
+            }
+            Instruction::Output { source } => format!("out {}", source.format(register_names)),
+        }
+    }
+}
+
+/// Represents the different reasons that a simple computer program can fail to assemble.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AssemblyErrorKind {
+    UnexpectedCharacter(char),
+    UnknownMnemonic(String),
+    InvalidRegister(String),
+    InvalidOperand(String),
+    InvalidNumber(String),
+    InvalidTarget(String),
+    MissingOperand,
+    UnexpectedOperand(String),
+    DuplicateLabel(String),
+    UndefinedLabel(String),
+}
+
+impl fmt::Display for AssemblyErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssemblyErrorKind::UnexpectedCharacter(c) => write!(f, "unexpected character '{}'", c),
+            AssemblyErrorKind::UnknownMnemonic(s) => write!(f, "unknown mnemonic \"{}\"", s),
+            AssemblyErrorKind::InvalidRegister(s) => write!(f, "invalid register \"{}\"", s),
+            AssemblyErrorKind::InvalidOperand(s) => write!(f, "invalid operand \"{}\"", s),
+            AssemblyErrorKind::InvalidNumber(s) => write!(f, "invalid number \"{}\"", s),
+            AssemblyErrorKind::InvalidTarget(s) => write!(f, "invalid jump target \"{}\"", s),
+            AssemblyErrorKind::MissingOperand => write!(f, "missing operand"),
+            AssemblyErrorKind::UnexpectedOperand(s) => write!(f, "unexpected operand \"{}\"", s),
+            AssemblyErrorKind::DuplicateLabel(s) => write!(f, "duplicate label \"{}\"", s),
+            AssemblyErrorKind::UndefinedLabel(s) => write!(f, "undefined label \"{}\"", s),
+        }
+    }
+}
+
+/// Represents an error found while assembling a simple computer program, along with the line
+/// (numbered from 1) where the error was found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssemblyError {
+    line_number: usize,
+    line: String,
+    kind: AssemblyErrorKind,
+}
+
+impl AssemblyError {
+    /// Gets the value of the "line_number" field.
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// Gets the value of the "line" field.
+    pub fn line(&self) -> &str {
+        &self.line
+    }
+
+    /// Gets the value of the "kind" field.
+    pub fn kind(&self) -> &AssemblyErrorKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for AssemblyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}: {} // {}",
+            self.line_number,
+            self.kind,
+            self.line.trim()
+        )
+    }
+}
+
+/// Represents the tokens that can appear in a line of a simple computer program.
+#[derive(Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Number(isize, String), // value and the text it was parsed from
+    InvalidNumber(String), // sign or digits that do not form a valid number
+    Comma,
+    Colon,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "{}", word),
+            Token::Number(_, text) | Token::InvalidNumber(text) => write!(f, "{}", text),
+            Token::Comma => write!(f, ","),
+            Token::Colon => write!(f, ":"),
+        }
+    }
+}
+
+/// Represents the jump target given in a jump instruction, before labels are resolved.
+enum Target {
+    Offset(isize),
+    Label(String),
+}
+
+/// Splits the line into tokens, ignoring any comment starting with "#" or ";".
+fn tokenize(line: &str) -> Result<Vec<Token>, AssemblyErrorKind> {
+    let mut tokens: Vec<Token> = vec![];
+    let mut chars = line.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            '#' | ';' => break,
+            ',' | ':' => {
+                chars.next();
+                tokens.push(if c == ',' { Token::Comma } else { Token::Colon });
+            }
+            '+' | '-' | '0'..='9' => {
+                let mut number = String::from(c);
+                chars.next();
+                while let Some(&d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+                    number.push(d);
+                    chars.next();
+                }
+                match number.parse::<isize>() {
+                    Ok(value) => tokens.push(Token::Number(value, number)),
+                    Err(_) => tokens.push(Token::InvalidNumber(number)),
+                }
+            }
+            _ if c.is_alphabetic() || c == '_' => {
+                let mut word = String::new();
+                while let Some(&d) = chars.peek().filter(|d| d.is_alphanumeric() || **d == '_') {
+                    word.push(d);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+            _ if c.is_whitespace() => _ = chars.next(),
+            _ => return Err(AssemblyErrorKind::UnexpectedCharacter(c)),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Takes the next operand from the tokens, skipping a comma separating it from the previous
+/// operand.
+fn next_operand(
+    tokens: &mut impl Iterator<Item = Token>,
+    first: bool,
+) -> Result<Token, AssemblyErrorKind> {
+    match tokens.next() {
+        Some(Token::Comma) if !first => tokens.next(),
+        token => token,
+    }
+    .ok_or(AssemblyErrorKind::MissingOperand)
+}
+
+/// Parses the register operand from the next tokens into the index of the register.
+fn parse_register(
+    tokens: &mut impl Iterator<Item = Token>,
+    first: bool,
+    register_names: &[String],
+) -> Result<usize, AssemblyErrorKind> {
+    match next_operand(tokens, first)? {
+        Token::Word(word) => match register_names.iter().position(|name| *name == word) {
+            Some(register) => Ok(register),
+            None => Err(AssemblyErrorKind::InvalidRegister(word)),
+        },
+        Token::InvalidNumber(text) => Err(AssemblyErrorKind::InvalidNumber(text)),
+        token => Err(AssemblyErrorKind::InvalidRegister(token.to_string())),
+    }
+}
+
+/// Parses the source operand (a register or specific value) from the next tokens.
+fn parse_source(
+    tokens: &mut impl Iterator<Item = Token>,
+    first: bool,
+    register_names: &[String],
+) -> Result<Operand, AssemblyErrorKind> {
+    match next_operand(tokens, first)? {
+        Token::Number(value, _) => Ok(Operand::Value(value)),
+        Token::Word(word) => match register_names.iter().position(|name| *name == word) {
+            Some(register) => Ok(Operand::Register(register)),
+            None => Err(AssemblyErrorKind::InvalidRegister(word)),
+        },
+        Token::InvalidNumber(text) => Err(AssemblyErrorKind::InvalidNumber(text)),
+        token => Err(AssemblyErrorKind::InvalidOperand(token.to_string())),
+    }
+}
+
+/// Parses the jump target operand (an offset or label) from the next tokens.
+fn parse_target(
+    tokens: &mut impl Iterator<Item = Token>,
+    first: bool,
+) -> Result<Target, AssemblyErrorKind> {
+    match next_operand(tokens, first)? {
+        Token::Number(offset, _) => Ok(Target::Offset(offset)),
+        Token::Word(label) => Ok(Target::Label(label)),
+        token => Err(AssemblyErrorKind::InvalidTarget(token.to_string())),
+    }
+}
+
+/// Assembles the simple computer program into its instructions, with register operands decoded
+/// into their index in the given register names. The program can contain blank lines, comments
+/// starting with "#" or ";" and labels (e.g., "loop:") that can be used as jump targets instead of
+/// offsets. Mnemonics are case-insensitive, and operands can be separated by commas and/or
+/// whitespace.
+pub(super) fn assemble(
+    raw_input: &str,
+    register_names: &[String],
+) -> Result<Vec<Instruction>, AssemblyError> {
+    let mut instructions: Vec<Instruction> = vec![];
+    let mut labels: HashMap<String, isize> = HashMap::new();
+    // Jump instructions with label targets, resolved once all labels are known
+    let mut label_targets: Vec<(usize, String, AssemblyError)> = vec![];
+    for (i, line) in raw_input.lines().enumerate() {
+        let error = |kind: AssemblyErrorKind| AssemblyError {
+            line_number: i + 1,
+            line: line.to_string(),
+            kind,
+        };
+        let mut tokens = tokenize(line).map_err(error)?.into_iter().peekable();
+        // Record labels at the start of the line against the address of the next instruction
+        while let Some(Token::Word(label)) = tokens.peek().cloned() {
+            let mut lookahead = tokens.clone();
+            lookahead.next();
+            if lookahead.next() != Some(Token::Colon) {
+                break;
+            }
+            if labels
+                .insert(label.to_string(), instructions.len() as isize)
+                .is_some()
+            {
+                return Err(error(AssemblyErrorKind::DuplicateLabel(label)));
+            }
+            tokens = lookahead;
+        }
+        // Parse the instruction, if the line has one
+        let mnemonic = match tokens.next() {
+            None => continue,
+            Some(Token::Word(word)) => word.to_lowercase(),
+            Some(Token::InvalidNumber(text)) => {
+                return Err(error(AssemblyErrorKind::InvalidNumber(text)))
+            }
+            Some(token) => {
+                return Err(error(AssemblyErrorKind::UnknownMnemonic(token.to_string())))
+            }
+        };
+        let mut instruction = match mnemonic.as_str() {
+            "hlf" | "tpl" | "inc" | "dec" | "jie" | "jio" => {
+                let register = parse_register(&mut tokens, true, register_names).map_err(error)?;
+                match mnemonic.as_str() {
+                    "hlf" => Instruction::Half { register },
+                    "tpl" => Instruction::Triple { register },
+                    "inc" => Instruction::Increment { register },
+                    "dec" => Instruction::Decrement { register },
+                    "jie" => Instruction::JumpIfEven {
+                        register,
+                        offset: 0,
+                    },
+                    _ => Instruction::JumpIfOne {
+                        register,
+                        offset: 0,
+                    },
+                }
+            }
+            "cpy" | "jnz" | "out" => {
+                let source = parse_source(&mut tokens, true, register_names).map_err(error)?;
+                match mnemonic.as_str() {
+                    "cpy" => Instruction::Copy {
+                        source,
+                        register: parse_register(&mut tokens, false, register_names)
+                            .map_err(error)?,
+                    },
+                    "jnz" => Instruction::JumpIfNotZero {
+                        condition: source,
+                        offset: 0,
+                    },
+                    _ => Instruction::Output { source },
+                }
+            }
+            "jmp" => Instruction::Jump { offset: 0 },
+            _ => return Err(error(AssemblyErrorKind::UnknownMnemonic(mnemonic))),
+        };
+        if instruction.offset().is_some() {
+            let first = matches!(instruction, Instruction::Jump { .. });
+            match parse_target(&mut tokens, first).map_err(error)? {
+                Target::Offset(offset) => instruction.set_offset(offset),
+                Target::Label(label) => {
+                    let undefined = error(AssemblyErrorKind::UndefinedLabel(label.to_string()));
+                    label_targets.push((instructions.len(), label, undefined));
+                }
+            }
+        }
+        if let Some(token) = tokens.next() {
+            return Err(error(AssemblyErrorKind::UnexpectedOperand(
+                token.to_string(),
+            )));
+        }
+        instructions.push(instruction);
+    }
+    // Resolve the label targets into offsets from the address of each jump instruction
+    for (address, label, undefined) in label_targets {
+        match labels.get(&label) {
+            Some(label_address) => {
+                instructions[address].set_offset(label_address - address as isize)
+            }
+            None => return Err(undefined),
+        }
+    }
+    Ok(instructions)
+}
+
+/// Disassembles the instructions into a program listing using the given register names, with one
+/// instruction per line preceded by its address. Jump instructions are followed by a comment giving
+/// the absolute address of the jump target.
+pub(super) fn disassemble(instructions: &[Instruction], register_names: &[String]) -> String {
+    let mut output = String::new();
+    for (address, instruction) in instructions.iter().enumerate() {
+        let line = match instruction.offset() {
+            Some(offset) => format!(
+                "{:04}  {:<12} ; -> {:04}",
+                address,
+                instruction.format(register_names),
+                address as isize + offset
+            ),
+            None => format!("{:04}  {}", address, instruction.format(register_names)),
+        };
+        output.push_str(&line);
+        output.push('\n');
+    }
+    output
+}
@@ -0,0 +1,238 @@
+use super::{Difficulty, MagicEntity, Spell};
+
+/// Represents an action taken by an enemy on its turn in the Wizard Simulator 20XX.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EnemyAction {
+    Attack,
+    Cast(Spell),
+}
+
+/// Represents the conditions checked by a scripted enemy before taking an action.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScriptCondition {
+    Always,
+    HealthBelow(i64),         // health of the enemy is below the value
+    PlayerHealthBelow(i64),   // health of the player is below the value
+    PlayerArmourAtLeast(i64), // armour of the player is at least the value
+}
+
+impl ScriptCondition {
+    /// Checks if the condition holds for the given enemy and player.
+    pub fn holds(&self, enemy: &MagicEntity, player: &MagicEntity) -> bool {
+        match *self {
+            ScriptCondition::Always => true,
+            ScriptCondition::HealthBelow(health) => enemy.health() < health,
+            ScriptCondition::PlayerHealthBelow(health) => player.health() < health,
+            ScriptCondition::PlayerArmourAtLeast(armour) => player.armour() >= armour,
+        }
+    }
+}
+
+/// Represents the behaviour of an enemy in the Wizard Simulator 20XX.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EnemyAi {
+    Melee,                                       // attacks on every turn
+    Rotation(Vec<EnemyAction>),                  // takes the actions in order, then repeats
+    Script(Vec<(ScriptCondition, EnemyAction)>), // takes the first possible action that applies
+}
+
+/// Represents an enemy in the Wizard Simulator 20XX, with its starting state and behaviour. Enemies
+/// attack when their rotation reaches a spell they cannot cast, or when no scripted action applies.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Enemy {
+    name: String,
+    entity: MagicEntity,
+    ai: EnemyAi,
+}
+
+impl Enemy {
+    /// Creates a new enemy that attacks on every turn.
+    pub fn new(name: &str, entity: &MagicEntity) -> Enemy {
+        Enemy {
+            name: name.to_string(),
+            entity: *entity,
+            ai: EnemyAi::Melee,
+        }
+    }
+
+    /// Sets the behaviour of the enemy. Returns an error if a rotation is empty, or if the spells
+    /// used are not from the same spellbook.
+    pub fn with_ai(mut self, ai: EnemyAi) -> Result<Enemy, String> {
+        if ai == EnemyAi::Rotation(vec![]) {
+            return Err(format!("Empty rotation: {}", self.name));
+        }
+        self.ai = ai;
+        let spells = self.spells();
+        for (i, spell) in spells.iter().enumerate() {
+            if spells[..i]
+                .iter()
+                .any(|other| other.id() == spell.id() && other != spell)
+            {
+                return Err(format!("Spells from different spellbooks: {}", self.name));
+            }
+        }
+        Ok(self)
+    }
+
+    /// Gets the value of the "name" field.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the value of the "entity" field.
+    pub fn entity(&self) -> &MagicEntity {
+        &self.entity
+    }
+
+    /// Gets the value of the "ai" field.
+    pub fn ai(&self) -> &EnemyAi {
+        &self.ai
+    }
+
+    /// Gets the spells that can be cast by the enemy.
+    pub fn spells(&self) -> Vec<&Spell> {
+        let actions: Vec<&EnemyAction> = match &self.ai {
+            EnemyAi::Melee => vec![],
+            EnemyAi::Rotation(actions) => actions.iter().collect(),
+            EnemyAi::Script(rules) => rules.iter().map(|(_, action)| action).collect(),
+        };
+        actions
+            .into_iter()
+            .filter_map(|action| match action {
+                EnemyAction::Attack => None,
+                EnemyAction::Cast(spell) => Some(spell),
+            })
+            .collect()
+    }
+
+    /// Chooses the spell cast by the enemy on its turn, given the current state of the enemy and
+    /// the player and the position of the enemy in its rotation. Returned value is None if the
+    /// enemy attacks instead.
+    pub fn choose_spell(
+        &self,
+        enemy: &MagicEntity,
+        player: &MagicEntity,
+        position: usize,
+    ) -> Option<&Spell> {
+        let can_cast = |spell: &Spell| enemy.can_cast(spell) && !enemy.is_effect_active(spell);
+        match &self.ai {
+            EnemyAi::Melee => None,
+            EnemyAi::Rotation(actions) => match &actions[position % actions.len()] {
+                EnemyAction::Cast(spell) if can_cast(spell) => Some(spell),
+                _ => None,
+            },
+            EnemyAi::Script(rules) => rules
+                .iter()
+                .filter(|(condition, _)| condition.holds(enemy, player))
+                .find_map(|(_, action)| match action {
+                    EnemyAction::Attack => Some(None),
+                    EnemyAction::Cast(spell) if can_cast(spell) => Some(Some(spell)),
+                    EnemyAction::Cast(_) => None,
+                })
+                .flatten(),
+        }
+    }
+
+    /// Gets the position of the enemy in its rotation after taking its turn from the given
+    /// position. Enemies without a rotation always stay at position 0.
+    pub fn next_position(&self, position: usize) -> usize {
+        match &self.ai {
+            EnemyAi::Rotation(actions) => (position + 1) % actions.len(),
+            _ => 0,
+        }
+    }
+}
+
+/// Represents a fight in the Wizard Simulator 20XX between the player and one or more enemies.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Encounter {
+    enemies: Vec<Enemy>,
+}
+
+impl Encounter {
+    /// Creates a new encounter with the given enemies. Returns an error if there are no enemies or
+    /// the enemy names are not unique.
+    pub fn new(enemies: &[Enemy]) -> Result<Encounter, String> {
+        if enemies.is_empty() {
+            return Err(String::from("Encounter has no enemies"));
+        }
+        for (i, enemy) in enemies.iter().enumerate() {
+            if enemies[..i].iter().any(|other| other.name == enemy.name) {
+                return Err(format!("Duplicate enemy name: {}", enemy.name));
+            }
+        }
+        Ok(Encounter {
+            enemies: enemies.to_vec(),
+        })
+    }
+
+    /// Creates the encounter from AOC 2015 Day 22, with a single boss that attacks on every turn.
+    pub fn single(boss: &MagicEntity) -> Encounter {
+        Encounter {
+            enemies: vec![Enemy::new("Boss", boss)],
+        }
+    }
+
+    /// Gets the value of the "enemies" field.
+    pub fn enemies(&self) -> &[Enemy] {
+        &self.enemies
+    }
+
+    /// Gets the state at the start of the encounter, with the player prepared by the difficulty.
+    pub fn start(&self, player: &MagicEntity, difficulty: &Difficulty) -> EncounterState {
+        let mut player = *player;
+        difficulty.prepare_player(&mut player);
+        EncounterState {
+            player,
+            enemies: self.enemies.iter().map(|enemy| enemy.entity).collect(),
+            positions: vec![0; self.enemies.len()],
+        }
+    }
+}
+
+/// Represents the state of an encounter at the start of a turn. Enemies are given in the same
+/// order as in the encounter.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EncounterState {
+    pub(super) player: MagicEntity,
+    pub(super) enemies: Vec<MagicEntity>,
+    pub(super) positions: Vec<usize>, // position of each enemy in its rotation
+}
+
+impl EncounterState {
+    /// Gets the value of the "player" field.
+    pub fn player(&self) -> &MagicEntity {
+        &self.player
+    }
+
+    /// Gets the value of the "enemies" field.
+    pub fn enemies(&self) -> &[MagicEntity] {
+        &self.enemies
+    }
+
+    /// Gets the value of the "positions" field.
+    pub fn positions(&self) -> &[usize] {
+        &self.positions
+    }
+
+    /// Gets a copy of the state with the total mana spent by the player reset to 0, so that states
+    /// reached by spending different amounts of mana can be compared.
+    pub fn without_mana_spent(&self) -> EncounterState {
+        EncounterState {
+            player: self.player.without_mana_spent(),
+            ..self.clone()
+        }
+    }
+
+    /// Checks if the enemy with the given index exists and is still alive.
+    pub fn is_enemy_alive(&self, index: usize) -> bool {
+        self.enemies
+            .get(index)
+            .is_some_and(|enemy| !enemy.is_dead())
+    }
+
+    /// Checks if all of the enemies have been defeated.
+    pub fn enemies_defeated(&self) -> bool {
+        self.enemies.iter().all(|enemy| enemy.is_dead())
+    }
+}
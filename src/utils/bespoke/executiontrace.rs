@@ -0,0 +1,314 @@
+use std::fmt;
+
+use serde_json::{json, Value};
+
+/// Represents a single instruction executed by the simple computer, along with the program counter
+/// and register values before and after the instruction was executed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceEntry {
+    step: u64,
+    pc: isize,
+    instruction: String,
+    next_pc: isize,
+    registers_before: Vec<isize>,
+    registers_after: Vec<isize>,
+}
+
+impl TraceEntry {
+    pub fn new(
+        step: u64,
+        pc: isize,
+        instruction: &str,
+        next_pc: isize,
+        registers_before: &[isize],
+        registers_after: &[isize],
+    ) -> TraceEntry {
+        TraceEntry {
+            step,
+            pc,
+            instruction: instruction.to_string(),
+            next_pc,
+            registers_before: registers_before.to_vec(),
+            registers_after: registers_after.to_vec(),
+        }
+    }
+
+    /// Gets the value of the "step" field.
+    pub fn step(&self) -> u64 {
+        self.step
+    }
+
+    /// Gets the value of the "pc" field.
+    pub fn pc(&self) -> isize {
+        self.pc
+    }
+
+    /// Gets the value of the "instruction" field.
+    pub fn instruction(&self) -> &str {
+        &self.instruction
+    }
+
+    /// Gets the value of the "next_pc" field.
+    pub fn next_pc(&self) -> isize {
+        self.next_pc
+    }
+
+    /// Gets the value of the "registers_before" field.
+    pub fn registers_before(&self) -> &[isize] {
+        &self.registers_before
+    }
+
+    /// Gets the value of the "registers_after" field.
+    pub fn registers_after(&self) -> &[isize] {
+        &self.registers_after
+    }
+}
+
+/// Represents the first point at which two execution traces differ. An entry is None if its trace
+/// ended before the point of divergence.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceDivergence {
+    index: usize,
+    left: Option<TraceEntry>,
+    right: Option<TraceEntry>,
+}
+
+impl TraceDivergence {
+    /// Gets the value of the "index" field.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Gets the value of the "left" field.
+    pub fn left(&self) -> Option<&TraceEntry> {
+        self.left.as_ref()
+    }
+
+    /// Gets the value of the "right" field.
+    pub fn right(&self) -> Option<&TraceEntry> {
+        self.right.as_ref()
+    }
+}
+
+/// Represents the record of the instructions executed by the simple computer while tracing is
+/// enabled.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExecutionTrace {
+    register_names: Vec<String>,
+    entries: Vec<TraceEntry>,
+}
+
+impl ExecutionTrace {
+    pub fn new(register_names: &[&str]) -> ExecutionTrace {
+        ExecutionTrace {
+            register_names: register_names.iter().map(|name| name.to_string()).collect(),
+            entries: vec![],
+        }
+    }
+
+    /// Gets the value of the "register_names" field.
+    pub fn register_names(&self) -> &[String] {
+        &self.register_names
+    }
+
+    /// Gets the value of the "entries" field.
+    pub fn entries(&self) -> &[TraceEntry] {
+        &self.entries
+    }
+
+    /// Adds the entry to the end of the trace.
+    pub fn push(&mut self, entry: TraceEntry) {
+        self.entries.push(entry);
+    }
+
+    /// Finds the first entry at which the trace differs from the other trace. Traces recorded with
+    /// different register names (or the same names in a different order) diverge at the first
+    /// entry, unless both traces are empty. Returns None if the traces are identical.
+    pub fn first_divergence(&self, other: &ExecutionTrace) -> Option<TraceDivergence> {
+        let len = self.entries.len().max(other.entries.len());
+        if len > 0 && self.register_names != other.register_names {
+            return Some(TraceDivergence {
+                index: 0,
+                left: self.entries.first().cloned(),
+                right: other.entries.first().cloned(),
+            });
+        }
+        (0..len)
+            .find(|&i| self.entries.get(i) != other.entries.get(i))
+            .map(|index| TraceDivergence {
+                index,
+                left: self.entries.get(index).cloned(),
+                right: other.entries.get(index).cloned(),
+            })
+    }
+
+    /// Converts the trace into JSON lines format, with a header object listing the register names
+    /// in order followed by one JSON object per entry.
+    pub fn to_json_lines(&self) -> String {
+        let mut output = json!({ "registers": self.register_names }).to_string();
+        output.push('\n');
+        for entry in self.entries.iter() {
+            let registers = |values: &[isize]| {
+                let map = self.register_names.iter().cloned().zip(values.iter());
+                Value::Object(map.map(|(name, value)| (name, json!(value))).collect())
+            };
+            let line = json!({
+                "step": entry.step,
+                "pc": entry.pc,
+                "instruction": entry.instruction,
+                "next_pc": entry.next_pc,
+                "before": registers(&entry.registers_before),
+                "after": registers(&entry.registers_after),
+            });
+            output.push_str(&line.to_string());
+            output.push('\n');
+        }
+        output
+    }
+
+    /// Converts the trace into CSV format, with a header line followed by one line per entry.
+    pub fn to_csv(&self) -> String {
+        let mut header = vec![String::from("step,pc,instruction,next_pc")];
+        header.extend(
+            self.register_names
+                .iter()
+                .map(|name| format!("{}_before", name)),
+        );
+        header.extend(
+            self.register_names
+                .iter()
+                .map(|name| format!("{}_after", name)),
+        );
+        let mut output = header.join(",");
+        output.push('\n');
+        for entry in self.entries.iter() {
+            let mut fields = vec![
+                entry.step.to_string(),
+                entry.pc.to_string(),
+                format!("\"{}\"", entry.instruction.replace('"', "\"\"")),
+                entry.next_pc.to_string(),
+            ];
+            fields.extend(entry.registers_before.iter().map(|value| value.to_string()));
+            fields.extend(entry.registers_after.iter().map(|value| value.to_string()));
+            output.push_str(&fields.join(","));
+            output.push('\n');
+        }
+        output
+    }
+
+    /// Parses a trace from JSON lines format. Returns None if the input is not a valid trace,
+    /// including if the header line listing the register names is missing.
+    pub fn from_json_lines(raw_input: &str) -> Option<ExecutionTrace> {
+        let mut lines = raw_input.lines().filter(|line| !line.trim().is_empty());
+        // Extract the register names from the header line
+        let header: Value = serde_json::from_str(lines.next()?).ok()?;
+        let register_names = header["registers"]
+            .as_array()?
+            .iter()
+            .map(|name| name.as_str().map(|name| name.to_string()))
+            .collect::<Option<Vec<String>>>()?;
+        let mut entries: Vec<TraceEntry> = vec![];
+        for line in lines {
+            let value: Value = serde_json::from_str(line).ok()?;
+            let before = value["before"].as_object()?;
+            let after = value["after"].as_object()?;
+            let registers = |map: &serde_json::Map<String, Value>| {
+                register_names
+                    .iter()
+                    .map(|name| map.get(name)?.as_i64().map(|value| value as isize))
+                    .collect::<Option<Vec<isize>>>()
+            };
+            entries.push(TraceEntry {
+                step: value["step"].as_u64()?,
+                pc: value["pc"].as_i64()? as isize,
+                instruction: value["instruction"].as_str()?.to_string(),
+                next_pc: value["next_pc"].as_i64()? as isize,
+                registers_before: registers(before)?,
+                registers_after: registers(after)?,
+            });
+        }
+        Some(ExecutionTrace {
+            register_names,
+            entries,
+        })
+    }
+
+    /// Parses a trace from CSV format. Returns None if the input is not a valid trace.
+    pub fn from_csv(raw_input: &str) -> Option<ExecutionTrace> {
+        let mut lines = raw_input.lines().filter(|line| !line.trim().is_empty());
+        // Extract the register names from the header line
+        let header = split_csv_line(lines.next()?)?;
+        let register_names: Vec<String> = header
+            .iter()
+            .filter_map(|field| field.strip_suffix("_before"))
+            .map(|name| name.to_string())
+            .collect();
+        let num_registers = register_names.len();
+        if header.len() != 4 + 2 * num_registers {
+            return None;
+        }
+        let mut entries: Vec<TraceEntry> = vec![];
+        for line in lines {
+            let fields = split_csv_line(line)?;
+            if fields.len() != header.len() {
+                return None;
+            }
+            let registers = fields[4..]
+                .iter()
+                .map(|field| field.parse::<isize>().ok())
+                .collect::<Option<Vec<isize>>>()?;
+            entries.push(TraceEntry {
+                step: fields[0].parse::<u64>().ok()?,
+                pc: fields[1].parse::<isize>().ok()?,
+                instruction: fields[2].to_string(),
+                next_pc: fields[3].parse::<isize>().ok()?,
+                registers_before: registers[..num_registers].to_vec(),
+                registers_after: registers[num_registers..].to_vec(),
+            });
+        }
+        Some(ExecutionTrace {
+            register_names,
+            entries,
+        })
+    }
+}
+
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "step {} | pc {} | {} | next pc {} | registers {:?} -> {:?}",
+            self.step,
+            self.pc,
+            self.instruction,
+            self.next_pc,
+            self.registers_before,
+            self.registers_after
+        )
+    }
+}
+
+/// Splits the CSV line into its fields, removing the quotes around quoted fields. Returns None if
+/// a quoted field is not closed.
+fn split_csv_line(line: &str) -> Option<Vec<String>> {
+    let mut fields: Vec<String> = vec![];
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.trim().chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return None;
+    }
+    fields.push(field);
+    Some(fields)
+}
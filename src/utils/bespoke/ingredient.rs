@@ -1,52 +1,72 @@
+use fancy_regex::Regex;
+use lazy_static::lazy_static;
+
+lazy_static! {
+    static ref REGEX_INGREDIENT: Regex = Regex::new(r"^(\S+): (.*)$").unwrap();
+    static ref REGEX_PROPERTY: Regex = Regex::new(r"^([a-z]+) (-?\d+)$").unwrap();
+}
+
 /// Represents a single ingredient as described in the AOC 2015 Day 15 problem
-/// (<https://adventofcode.com/2015/day/15>).
+/// (<https://adventofcode.com/2015/day/15>), with any number of named properties.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Ingredient {
-    capacity: i64,
-    durability: i64,
-    flavour: i64,
-    texture: i64,
-    calories: i64,
+    name: String,
+    properties: Vec<(String, i64)>, // property values per teaspoon, in the order given
 }
 
 impl Ingredient {
-    pub fn new(
-        capacity: i64,
-        durability: i64,
-        flavour: i64,
-        texture: i64,
-        calories: i64,
-    ) -> Ingredient {
+    pub fn new(name: &str, properties: &[(&str, i64)]) -> Ingredient {
         Ingredient {
-            capacity,
-            durability,
-            flavour,
-            texture,
-            calories,
+            name: name.to_string(),
+            properties: properties
+                .iter()
+                .map(|(property, value)| (property.to_string(), *value))
+                .collect(),
         }
     }
 
-    /// Gets the value of the "capacity" field.
-    pub fn capacity(&self) -> i64 {
-        self.capacity
-    }
-
-    /// Gets the value of the "durability" field.
-    pub fn durability(&self) -> i64 {
-        self.durability
+    /// Parses the ingredient from the format used in the problem input (e.g., "Sugar: capacity -1,
+    /// durability 0, flavor 0, texture 2, calories 8"). Returns an error if the line is not in the
+    /// expected format or a property is given more than once.
+    pub fn parse(line: &str) -> Result<Ingredient, String> {
+        let caps = match REGEX_INGREDIENT.captures(line.trim()) {
+            Ok(Some(caps)) => caps,
+            _ => return Err(format!("Invalid ingredient line: {}", line)),
+        };
+        let mut properties: Vec<(String, i64)> = vec![];
+        for property in caps[2].split(", ") {
+            let prop_caps = match REGEX_PROPERTY.captures(property) {
+                Ok(Some(prop_caps)) => prop_caps,
+                _ => return Err(format!("Invalid ingredient property: {}", property)),
+            };
+            if properties.iter().any(|(name, _)| name == &prop_caps[1]) {
+                return Err(format!("Duplicate ingredient property: {}", &prop_caps[1]));
+            }
+            let value = prop_caps[2].parse::<i64>().map_err(|e| e.to_string())?;
+            properties.push((prop_caps[1].to_string(), value));
+        }
+        Ok(Ingredient {
+            name: caps[1].to_string(),
+            properties,
+        })
     }
 
-    /// Gets the value of the "flavour" field.
-    pub fn flavour(&self) -> i64 {
-        self.flavour
+    /// Gets the value of the "name" field.
+    pub fn name(&self) -> &str {
+        &self.name
     }
 
-    /// Gets the value of the "texture" field.
-    pub fn texture(&self) -> i64 {
-        self.texture
+    /// Gets the value of the "properties" field.
+    pub fn properties(&self) -> &[(String, i64)] {
+        &self.properties
     }
 
-    /// Gets the value of the "calories" field.
-    pub fn calories(&self) -> i64 {
-        self.calories
+    /// Gets the value of the named property per teaspoon of the ingredient. Returned value is None
+    /// if the ingredient does not have the property.
+    pub fn property(&self, property: &str) -> Option<i64> {
+        self.properties
+            .iter()
+            .find(|(name, _)| name == property)
+            .map(|(_, value)| *value)
     }
 }
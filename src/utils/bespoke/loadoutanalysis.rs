@@ -0,0 +1,111 @@
+use super::{Loadout, RpgEntity, Shop};
+
+/// Represents the outcome of the fight against an enemy for a player holding a loadout.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoadoutOutcome {
+    loadout: Loadout,
+    margin: i64, // turns to spare when winning (negative when losing)
+}
+
+impl LoadoutOutcome {
+    /// Gets the value of the "loadout" field.
+    pub fn loadout(&self) -> &Loadout {
+        &self.loadout
+    }
+
+    /// Gets the value of the "margin" field.
+    pub fn margin(&self) -> i64 {
+        self.margin
+    }
+
+    /// Gets the total cost of the loadout.
+    pub fn cost(&self) -> i64 {
+        self.loadout.cost()
+    }
+
+    /// Checks if the player holding the loadout wins the fight.
+    pub fn is_win(&self) -> bool {
+        self.margin >= 0
+    }
+
+    /// Gets the names of the items in the loadout.
+    pub fn item_names(&self) -> Vec<&str> {
+        self.loadout
+            .items()
+            .iter()
+            .map(|item| item.name())
+            .collect()
+    }
+}
+
+/// Represents the analysis of every loadout that can be bought from a shop in RPG Simulator 20XX,
+/// giving the outcome of the fight against an enemy for each loadout. The margin of each outcome
+/// is the number of turns the enemy needs to defeat the player less the number of turns the player
+/// needs to defeat the enemy. The player attacks first, so they win if the margin is not negative.
+/// Health regenerated by items is not taken into account (see `simulate_duel` for fights with
+/// regeneration).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoadoutAnalysis {
+    outcomes: Vec<LoadoutOutcome>,
+}
+
+impl LoadoutAnalysis {
+    /// Analyses each of the loadouts from the shop for the player with the given starting health
+    /// fighting the enemy.
+    pub fn new(shop: &Shop, player_health: i64, enemy: &RpgEntity) -> LoadoutAnalysis {
+        let outcomes = shop
+            .loadouts()
+            .into_iter()
+            .map(|loadout| {
+                let player = RpgEntity::new(player_health, loadout.damage(), loadout.armour());
+                let player_turns = player.turns_to_defeat(enemy) as i64;
+                let enemy_turns = enemy.turns_to_defeat(&player) as i64;
+                LoadoutOutcome {
+                    loadout,
+                    margin: enemy_turns - player_turns,
+                }
+            })
+            .collect();
+        LoadoutAnalysis { outcomes }
+    }
+
+    /// Gets the value of the "outcomes" field.
+    pub fn outcomes(&self) -> &[LoadoutOutcome] {
+        &self.outcomes
+    }
+
+    /// Gets the cost-vs-margin frontier of the loadouts, in order of increasing cost. Each outcome
+    /// on the frontier has a greater margin than every cheaper loadout, and the greatest margin of
+    /// the loadouts with the same cost (ties broken by the order of the loadouts).
+    pub fn frontier(&self) -> Vec<&LoadoutOutcome> {
+        let mut outcomes: Vec<&LoadoutOutcome> = self.outcomes.iter().collect();
+        outcomes.sort_by_key(|outcome| (outcome.cost(), -outcome.margin));
+        let mut frontier: Vec<&LoadoutOutcome> = vec![];
+        for outcome in outcomes {
+            let dominated = frontier
+                .last()
+                .is_some_and(|last| outcome.margin <= last.margin);
+            if !dominated {
+                frontier.push(outcome);
+            }
+        }
+        frontier
+    }
+
+    /// Gets the cheapest loadout with which the player wins with at least the given number of turns
+    /// to spare. Returned value is None if no loadout wins by the margin.
+    pub fn cheapest_with_margin(&self, min_margin: i64) -> Option<&LoadoutOutcome> {
+        self.frontier()
+            .into_iter()
+            .find(|outcome| outcome.margin >= min_margin)
+    }
+
+    /// Gets the most expensive loadout with a margin below the given value (a margin below 0 being
+    /// a loss). Returned value is None if every loadout has at least the given margin.
+    pub fn costliest_below_margin(&self, margin: i64) -> Option<&LoadoutOutcome> {
+        self.outcomes
+            .iter()
+            .filter(|outcome| outcome.margin < margin)
+            .max_by_key(|outcome| outcome.cost())
+    }
+}
@@ -0,0 +1,834 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use fancy_regex::Regex;
+use lazy_static::lazy_static;
+
+lazy_static! {
+    static ref REGEX_VALUE: Regex = Regex::new(r"^([a-z]+|\d+) -> ([a-z]+)$").unwrap();
+    static ref REGEX_UNARY: Regex = Regex::new(r"^NOT ([a-z]+|\d+) -> ([a-z]+)$").unwrap();
+    static ref REGEX_MUX: Regex =
+        Regex::new(r"^MUX ([a-z]+|\d+) ([a-z]+|\d+) ([a-z]+|\d+) -> ([a-z]+)$").unwrap();
+    static ref REGEX_BINARY: Regex =
+        Regex::new(r"^([a-z]+|\d+) (AND|NAND|LSHIFT|RSHIFT|OR|NOR|XOR) ([a-z]+|\d+) -> ([a-z]+)$")
+            .unwrap();
+}
+
+/// Represents the supported bit widths of the signals carried by the wires in a circuit.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BitWidth {
+    Eight,
+    Sixteen,
+    ThirtyTwo,
+    SixtyFour,
+}
+
+impl BitWidth {
+    /// Gets the bit width with the given number of bits, if it is supported.
+    pub fn from_bits(bits: u32) -> Option<BitWidth> {
+        match bits {
+            8 => Some(BitWidth::Eight),
+            16 => Some(BitWidth::Sixteen),
+            32 => Some(BitWidth::ThirtyTwo),
+            64 => Some(BitWidth::SixtyFour),
+            _ => None,
+        }
+    }
+
+    /// Gets the number of bits in a signal of the bit width.
+    pub fn bits(&self) -> u32 {
+        self.mask().count_ones()
+    }
+
+    /// Gets the bitmask covering all of the bits in a signal of the bit width.
+    pub fn mask(&self) -> u64 {
+        match self {
+            BitWidth::Eight => u8::MAX as u64,
+            BitWidth::Sixteen => u16::MAX as u64,
+            BitWidth::ThirtyTwo => u32::MAX as u64,
+            BitWidth::SixtyFour => u64::MAX,
+        }
+    }
+}
+
+/// Represents the different operations (gates) that can provide the value feeding into a wire.
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Operation {
+    Value {
+        left: String,
+    },
+    And {
+        left: String,
+        right: String,
+    },
+    LShift {
+        left: String,
+        right: String,
+    },
+    RShift {
+        left: String,
+        right: String,
+    },
+    Not {
+        left: String,
+    },
+    Or {
+        left: String,
+        right: String,
+    },
+    Xor {
+        left: String,
+        right: String,
+    },
+    Nand {
+        left: String,
+        right: String,
+    },
+    Nor {
+        left: String,
+        right: String,
+    },
+    Mux {
+        select: String,
+        left: String,
+        right: String,
+    },
+}
+
+impl Operation {
+    /// Gets the input terms of the operation.
+    fn terms(&self) -> Vec<&String> {
+        match self {
+            Operation::Value { left } | Operation::Not { left } => vec![left],
+            Operation::And { left, right }
+            | Operation::LShift { left, right }
+            | Operation::RShift { left, right }
+            | Operation::Or { left, right }
+            | Operation::Xor { left, right }
+            | Operation::Nand { left, right }
+            | Operation::Nor { left, right } => vec![left, right],
+            Operation::Mux {
+                select,
+                left,
+                right,
+            } => vec![select, left, right],
+        }
+    }
+
+    /// Returns a copy of the operation with each of its input terms replaced by the result of the
+    /// given function.
+    fn map_terms(&self, f: impl Fn(&String) -> String) -> Operation {
+        match self {
+            Operation::Value { left } => Operation::Value { left: f(left) },
+            Operation::Not { left } => Operation::Not { left: f(left) },
+            Operation::And { left, right } => Operation::And {
+                left: f(left),
+                right: f(right),
+            },
+            Operation::LShift { left, right } => Operation::LShift {
+                left: f(left),
+                right: f(right),
+            },
+            Operation::RShift { left, right } => Operation::RShift {
+                left: f(left),
+                right: f(right),
+            },
+            Operation::Or { left, right } => Operation::Or {
+                left: f(left),
+                right: f(right),
+            },
+            Operation::Xor { left, right } => Operation::Xor {
+                left: f(left),
+                right: f(right),
+            },
+            Operation::Nand { left, right } => Operation::Nand {
+                left: f(left),
+                right: f(right),
+            },
+            Operation::Nor { left, right } => Operation::Nor {
+                left: f(left),
+                right: f(right),
+            },
+            Operation::Mux {
+                select,
+                left,
+                right,
+            } => Operation::Mux {
+                select: f(select),
+                left: f(left),
+                right: f(right),
+            },
+        }
+    }
+
+    /// Gets the canonical form of the operation, with the inputs of commutative gates placed in
+    /// sorted order. Operations with equal canonical forms always produce the same value.
+    fn canonical(&self) -> Operation {
+        let sorted = |left: &String, right: &String| {
+            if left <= right {
+                (left.to_string(), right.to_string())
+            } else {
+                (right.to_string(), left.to_string())
+            }
+        };
+        match self {
+            Operation::And { left, right } => {
+                let (left, right) = sorted(left, right);
+                Operation::And { left, right }
+            }
+            Operation::Or { left, right } => {
+                let (left, right) = sorted(left, right);
+                Operation::Or { left, right }
+            }
+            Operation::Xor { left, right } => {
+                let (left, right) = sorted(left, right);
+                Operation::Xor { left, right }
+            }
+            Operation::Nand { left, right } => {
+                let (left, right) = sorted(left, right);
+                Operation::Nand { left, right }
+            }
+            Operation::Nor { left, right } => {
+                let (left, right) = sorted(left, right);
+                Operation::Nor { left, right }
+            }
+            _ => self.clone(),
+        }
+    }
+
+    /// Evaluates the operation, using the given function to get the value of each input term.
+    /// Returns None if the value of any input term cannot be determined. The result is truncated
+    /// to the given bit width.
+    fn evaluate(
+        &self,
+        mut term: impl FnMut(&String) -> Option<u64>,
+        width: BitWidth,
+    ) -> Option<u64> {
+        let value = match self {
+            Operation::Value { left } => term(left)?,
+            Operation::And { left, right } => term(left)? & term(right)?,
+            Operation::LShift { left, right } => shift_left(term(left)?, term(right)?),
+            Operation::RShift { left, right } => shift_right(term(left)?, term(right)?),
+            Operation::Not { left } => !term(left)?,
+            Operation::Or { left, right } => term(left)? | term(right)?,
+            Operation::Xor { left, right } => term(left)? ^ term(right)?,
+            Operation::Nand { left, right } => !(term(left)? & term(right)?),
+            Operation::Nor { left, right } => !(term(left)? | term(right)?),
+            Operation::Mux {
+                select,
+                left,
+                right,
+            } => {
+                // Each bit is taken from the left input if set in the select input, else the right
+                let select = term(select)?;
+                (select & term(left)?) | (!select & term(right)?)
+            }
+        };
+        Some(value & width.mask())
+    }
+
+    /// Simplifies the operation by folding it into a value if all of its inputs are values, or by
+    /// removing the gate if it has no effect on one of its inputs.
+    fn simplify(&self, width: BitWidth) -> Operation {
+        // Fold operations with inputs that are all specific values
+        if let Some(value) = self.evaluate(|term| literal_value(term, width), width) {
+            return Operation::Value {
+                left: value.to_string(),
+            };
+        }
+        let is = |term: &String, value: u64| literal_value(term, width) == Some(value);
+        let is_at_least = |term: &String, value: u64| {
+            literal_value(term, width).is_some_and(|term_value| term_value >= value)
+        };
+        let value = |term: &String| Operation::Value {
+            left: term.to_string(),
+        };
+        let not = |term: &String| Operation::Not {
+            left: term.to_string(),
+        };
+        let mask = width.mask();
+        let bits = width.bits() as u64;
+        match self {
+            Operation::And { left, right } => {
+                if is(left, 0) || is(right, 0) {
+                    value(&0.to_string())
+                } else if is(left, mask) || left == right {
+                    value(right)
+                } else if is(right, mask) {
+                    value(left)
+                } else {
+                    self.clone()
+                }
+            }
+            Operation::Or { left, right } => {
+                if is(left, mask) || is(right, mask) {
+                    value(&mask.to_string())
+                } else if is(left, 0) || left == right {
+                    value(right)
+                } else if is(right, 0) {
+                    value(left)
+                } else {
+                    self.clone()
+                }
+            }
+            Operation::Xor { left, right } => {
+                if left == right {
+                    value(&0.to_string())
+                } else if is(left, 0) {
+                    value(right)
+                } else if is(right, 0) {
+                    value(left)
+                } else {
+                    self.clone()
+                }
+            }
+            Operation::LShift { left, right } | Operation::RShift { left, right } => {
+                if is(left, 0) || is_at_least(right, bits) {
+                    value(&0.to_string())
+                } else if is(right, 0) {
+                    value(left)
+                } else {
+                    self.clone()
+                }
+            }
+            Operation::Nand { left, right } => {
+                if is(left, mask) {
+                    not(right)
+                } else if is(right, mask) || left == right {
+                    not(left)
+                } else {
+                    self.clone()
+                }
+            }
+            Operation::Nor { left, right } => {
+                if is(left, 0) {
+                    not(right)
+                } else if is(right, 0) || left == right {
+                    not(left)
+                } else {
+                    self.clone()
+                }
+            }
+            Operation::Mux {
+                select,
+                left,
+                right,
+            } => {
+                if is(select, mask) || left == right {
+                    value(left)
+                } else if is(select, 0) {
+                    value(right)
+                } else {
+                    self.clone()
+                }
+            }
+            Operation::Value { .. } | Operation::Not { .. } => self.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Operation::Value { left } => write!(f, "{}", left),
+            Operation::And { left, right } => write!(f, "{} AND {}", left, right),
+            Operation::LShift { left, right } => write!(f, "{} LSHIFT {}", left, right),
+            Operation::RShift { left, right } => write!(f, "{} RSHIFT {}", left, right),
+            Operation::Not { left } => write!(f, "NOT {}", left),
+            Operation::Or { left, right } => write!(f, "{} OR {}", left, right),
+            Operation::Xor { left, right } => write!(f, "{} XOR {}", left, right),
+            Operation::Nand { left, right } => write!(f, "{} NAND {}", left, right),
+            Operation::Nor { left, right } => write!(f, "{} NOR {}", left, right),
+            Operation::Mux {
+                select,
+                left,
+                right,
+            } => write!(f, "MUX {} {} {}", select, left, right),
+        }
+    }
+}
+
+/// Gets the value of the given term if it is a specific value rather than the name of a wire. The
+/// value is truncated to the given bit width.
+fn literal_value(term: &str, width: BitWidth) -> Option<u64> {
+    term.parse::<u64>().ok().map(|value| value & width.mask())
+}
+
+/// Shifts the value left by the given number of bits, giving 0 if all bits are shifted out.
+fn shift_left(value: u64, bits: u64) -> u64 {
+    u32::try_from(bits)
+        .ok()
+        .and_then(|bits| value.checked_shl(bits))
+        .unwrap_or(0)
+}
+
+/// Shifts the value right by the given number of bits, giving 0 if all bits are shifted out.
+fn shift_right(value: u64, bits: u64) -> u64 {
+    u32::try_from(bits)
+        .ok()
+        .and_then(|bits| value.checked_shr(bits))
+        .unwrap_or(0)
+}
+
+/// Represents the binary operators that can appear in a symbolic expression.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BinaryOperator {
+    And,
+    LShift,
+    RShift,
+    Or,
+    Xor,
+    Nand,
+    Nor,
+}
+
+/// Represents a node in the symbolic expression tree for the value provided to a wire. Wire nodes
+/// refer to either a free input of the circuit or a shared sub-expression bound to the wire name.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Expression {
+    Value(u64),
+    Wire(String),
+    Not(Box<Expression>),
+    Binary {
+        operator: BinaryOperator,
+        left: Box<Expression>,
+        right: Box<Expression>,
+    },
+    Mux {
+        select: Box<Expression>,
+        left: Box<Expression>,
+        right: Box<Expression>,
+    },
+}
+
+impl Expression {
+    /// Evaluates the expression using the given values for the wires it refers to. Returns None
+    /// if the expression refers to a wire without a value. The result is truncated to the given bit
+    /// width.
+    pub fn evaluate(&self, wire_values: &HashMap<String, u64>, width: BitWidth) -> Option<u64> {
+        let value = match self {
+            Expression::Value(value) => *value,
+            Expression::Wire(wire) => *wire_values.get(wire)?,
+            Expression::Not(left) => !left.evaluate(wire_values, width)?,
+            Expression::Binary {
+                operator,
+                left,
+                right,
+            } => {
+                let left = left.evaluate(wire_values, width)?;
+                let right = right.evaluate(wire_values, width)?;
+                match operator {
+                    BinaryOperator::And => left & right,
+                    BinaryOperator::LShift => shift_left(left, right),
+                    BinaryOperator::RShift => shift_right(left, right),
+                    BinaryOperator::Or => left | right,
+                    BinaryOperator::Xor => left ^ right,
+                    BinaryOperator::Nand => !(left & right),
+                    BinaryOperator::Nor => !(left | right),
+                }
+            }
+            Expression::Mux {
+                select,
+                left,
+                right,
+            } => {
+                let select = select.evaluate(wire_values, width)?;
+                let left = left.evaluate(wire_values, width)?;
+                let right = right.evaluate(wire_values, width)?;
+                (select & left) | (!select & right)
+            }
+        };
+        Some(value & width.mask())
+    }
+
+    /// Writes the expression as an operand of another expression, adding parentheses around it
+    /// unless it is a single value or wire.
+    fn fmt_operand(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expression::Value(_) | Expression::Wire(_) | Expression::Mux { .. } => {
+                write!(f, "{}", self)
+            }
+            _ => write!(f, "({})", self),
+        }
+    }
+}
+
+impl fmt::Display for Expression {
+    /// Writes the expression in infix form.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expression::Value(value) => write!(f, "{}", value),
+            Expression::Wire(wire) => write!(f, "{}", wire),
+            Expression::Not(left) => {
+                write!(f, "~")?;
+                left.fmt_operand(f)
+            }
+            Expression::Binary {
+                operator,
+                left,
+                right,
+            } => {
+                let symbol = match operator {
+                    BinaryOperator::And | BinaryOperator::Nand => "&",
+                    BinaryOperator::LShift => "<<",
+                    BinaryOperator::RShift => ">>",
+                    BinaryOperator::Or | BinaryOperator::Nor => "|",
+                    BinaryOperator::Xor => "^",
+                };
+                let negated = matches!(operator, BinaryOperator::Nand | BinaryOperator::Nor);
+                if negated {
+                    write!(f, "~(")?;
+                }
+                left.fmt_operand(f)?;
+                write!(f, " {} ", symbol)?;
+                right.fmt_operand(f)?;
+                if negated {
+                    write!(f, ")")?;
+                }
+                Ok(())
+            }
+            Expression::Mux {
+                select,
+                left,
+                right,
+            } => write!(f, "mux({}, {}, {})", select, left, right),
+        }
+    }
+}
+
+/// Represents the symbolic expression for the value provided to a wire, with sub-expressions
+/// that are used more than once bound to the name of the wire they feed.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SymbolicExpression {
+    bindings: Vec<(String, Expression)>,
+    result: Expression,
+}
+
+impl SymbolicExpression {
+    /// Gets the value of the "bindings" field. Each binding only refers to free inputs and the
+    /// bindings before it.
+    pub fn bindings(&self) -> &[(String, Expression)] {
+        &self.bindings
+    }
+
+    /// Gets the value of the "result" field.
+    pub fn result(&self) -> &Expression {
+        &self.result
+    }
+
+    /// Evaluates the symbolic expression using the given values for its free inputs. Returns None
+    /// if a free input has not been given a value.
+    pub fn evaluate(&self, inputs: &HashMap<String, u64>, width: BitWidth) -> Option<u64> {
+        let mut wire_values = inputs.clone();
+        for (wire, expression) in self.bindings.iter() {
+            let value = expression.evaluate(&wire_values, width)?;
+            wire_values.insert(wire.to_string(), value);
+        }
+        self.result.evaluate(&wire_values, width)
+    }
+}
+
+impl fmt::Display for SymbolicExpression {
+    /// Writes each shared sub-expression as a let-binding, followed by the resulting expression.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.bindings.is_empty() {
+            return write!(f, "{}", self.result);
+        }
+        for (wire, expression) in self.bindings.iter() {
+            writeln!(f, "let {} = {}", wire, expression)?;
+        }
+        write!(f, "in {}", self.result)
+    }
+}
+
+/// Represents a circuit of wires connected by bitwise logic gates, as used in AOC 2015 Day 7
+/// (https://adventofcode.com/2015/day/7).
+#[derive(Clone)]
+pub struct LogicCircuit {
+    wire_ops: HashMap<String, Operation>,
+    width: BitWidth,
+}
+
+impl LogicCircuit {
+    pub fn new(raw_input: &str, width: BitWidth) -> Option<LogicCircuit> {
+        let mut wire_ops: HashMap<String, Operation> = HashMap::new();
+        for line in raw_input.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            // Extract the wire and operation from the current line
+            if let Ok(Some(caps)) = REGEX_VALUE.captures(line) {
+                let left = caps[1].to_string();
+                let wire = caps[2].to_string();
+                wire_ops.insert(wire, Operation::Value { left });
+            } else if let Ok(Some(caps)) = REGEX_UNARY.captures(line) {
+                let left = caps[1].to_string();
+                let wire = caps[2].to_string();
+                wire_ops.insert(wire, Operation::Not { left });
+            } else if let Ok(Some(caps)) = REGEX_MUX.captures(line) {
+                let select = caps[1].to_string();
+                let left = caps[2].to_string();
+                let right = caps[3].to_string();
+                let wire = caps[4].to_string();
+                wire_ops.insert(
+                    wire,
+                    Operation::Mux {
+                        select,
+                        left,
+                        right,
+                    },
+                );
+            } else if let Ok(Some(caps)) = REGEX_BINARY.captures(line) {
+                let left = caps[1].to_string();
+                let op_type = &caps[2];
+                let right = caps[3].to_string();
+                let wire = caps[4].to_string();
+                let op = match op_type {
+                    "AND" => Operation::And { left, right },
+                    "NAND" => Operation::Nand { left, right },
+                    "OR" => Operation::Or { left, right },
+                    "NOR" => Operation::Nor { left, right },
+                    "XOR" => Operation::Xor { left, right },
+                    "LSHIFT" => Operation::LShift { left, right },
+                    "RSHIFT" => Operation::RShift { left, right },
+                    _ => return None,
+                };
+                wire_ops.insert(wire, op);
+            } else {
+                // Invalid line, so the logic circuit cannot be created
+                return None;
+            }
+        }
+        Some(LogicCircuit { wire_ops, width })
+    }
+
+    /// Gets the value of the "width" field.
+    pub fn width(&self) -> BitWidth {
+        self.width
+    }
+
+    /// Gets the number of gates in the circuit, with each wire being fed by a single gate.
+    pub fn gate_count(&self) -> usize {
+        self.wire_ops.len()
+    }
+
+    /// Replaces the gate feeding the given wire so that the wire is provided the given value.
+    pub fn set_wire_value(&mut self, wire: &str, value: u64) {
+        self.wire_ops.insert(
+            wire.to_string(),
+            Operation::Value {
+                left: (value & self.width.mask()).to_string(),
+            },
+        );
+    }
+
+    /// Determines the value provided to the target wire. Returns None if the target wire, or a
+    /// wire that it depends on, is not fed by any gate in the circuit.
+    pub fn evaluate_wire(&self, target_wire: &str) -> Option<u64> {
+        let mut wire_values: HashMap<String, u64> = HashMap::new();
+        self.evaluate_wire_recursive(target_wire, &mut wire_values)
+    }
+
+    /// Recursive support function used to determine the value provided to the target wire.
+    fn evaluate_wire_recursive(
+        &self,
+        target_wire: &str,
+        wire_values: &mut HashMap<String, u64>,
+    ) -> Option<u64> {
+        // Check if the wire value has already been found
+        if let Entry::Occupied(e) = wire_values.entry(target_wire.to_string()) {
+            return Some(*e.get());
+        }
+        // Calculate the value fed to the target wire
+        let wire_value = self.wire_ops.get(target_wire)?.evaluate(
+            |term| match literal_value(term, self.width) {
+                Some(value) => Some(value),
+                None => self.evaluate_wire_recursive(term, wire_values),
+            },
+            self.width,
+        )?;
+        // Records the value fed to the target wire
+        wire_values.insert(target_wire.to_string(), wire_value);
+        Some(wire_value)
+    }
+
+    /// Simplifies the circuit by folding constants, removing identity gates, merging duplicate
+    /// gates and dropping wires that do not feed any of the root wires. The gates feeding the free
+    /// input wires are left unchanged and their values are not propagated, so they can be
+    /// overridden in the simplified circuit. Returns the simplified circuit and the number of gates
+    /// eliminated.
+    pub fn simplify(&self, roots: &[&str], free_inputs: &[&str]) -> (LogicCircuit, usize) {
+        let is_free = |wire: &String| free_inputs.contains(&wire.as_str());
+        let mut wire_ops = self.wire_ops.clone();
+        loop {
+            let mut changed = false;
+            // Fold constants and remove identity gates
+            for (_, op) in wire_ops.iter_mut().filter(|(wire, _)| !is_free(wire)) {
+                let new_op = op.simplify(self.width);
+                changed |= new_op != *op;
+                *op = new_op;
+            }
+            // Replace uses of wires fed directly by a value or another wire
+            let aliases: HashMap<String, String> = wire_ops
+                .iter()
+                .filter(|(wire, _)| !is_free(wire))
+                .filter_map(|(wire, op)| match op {
+                    Operation::Value { left } => Some((wire.to_string(), left.to_string())),
+                    _ => None,
+                })
+                .collect();
+            for (_, op) in wire_ops.iter_mut().filter(|(wire, _)| !is_free(wire)) {
+                let new_op = op.map_terms(|term| aliases.get(term).unwrap_or(term).to_string());
+                changed |= new_op != *op;
+                *op = new_op;
+            }
+            // Merge gates that are duplicates of another gate
+            let mut wires: Vec<String> = wire_ops.keys().cloned().collect();
+            wires.sort();
+            let mut gates_seen: HashMap<Operation, String> = HashMap::new();
+            for wire in wires.into_iter().filter(|wire| !is_free(wire)) {
+                let op = &wire_ops[&wire];
+                if let Operation::Value { .. } = op {
+                    continue;
+                }
+                match gates_seen.entry(op.canonical()) {
+                    Entry::Occupied(e) => {
+                        let left = e.get().to_string();
+                        wire_ops.insert(wire, Operation::Value { left });
+                        changed = true;
+                    }
+                    Entry::Vacant(e) => _ = e.insert(wire),
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        // Drop wires that do not feed any of the root wires
+        let mut live_wires: HashSet<String> = HashSet::new();
+        let mut wires_to_visit: Vec<String> = roots.iter().map(|wire| wire.to_string()).collect();
+        while let Some(wire) = wires_to_visit.pop() {
+            if !live_wires.insert(wire.to_string()) {
+                continue;
+            }
+            if let Some(op) = wire_ops.get(&wire) {
+                for term in op.terms() {
+                    if literal_value(term, self.width).is_none() {
+                        wires_to_visit.push(term.to_string());
+                    }
+                }
+            }
+        }
+        wire_ops.retain(|wire, _| live_wires.contains(wire));
+        let simplified = LogicCircuit {
+            wire_ops,
+            width: self.width,
+        };
+        let gates_eliminated = self.gate_count() - simplified.gate_count();
+        (simplified, gates_eliminated)
+    }
+
+    /// Expands the target wire into a symbolic expression over the free inputs of the circuit,
+    /// being the given free input wires and any wires that are not fed by a gate. Constant
+    /// sub-expressions are folded before expansion, and sub-expressions used more than once are
+    /// shared using let-bindings.
+    pub fn expand_wire(&self, target_wire: &str, free_inputs: &[&str]) -> SymbolicExpression {
+        let (simplified, _) = self.simplify(&[target_wire], free_inputs);
+        let is_free =
+            |wire: &str| free_inputs.contains(&wire) || !simplified.wire_ops.contains_key(wire);
+        // Find the wires used by more than one gate in the simplified circuit
+        let mut use_counts: HashMap<&String, usize> = HashMap::new();
+        for op in simplified.wire_ops.values() {
+            for term in op.terms() {
+                *use_counts.entry(term).or_insert(0) += 1;
+            }
+        }
+        let shared_wires: HashSet<&String> = use_counts
+            .into_iter()
+            .filter(|(wire, count)| *count > 1 && !is_free(wire))
+            .map(|(wire, _)| wire)
+            .collect();
+        // Expand the target wire, binding shared sub-expressions as they are found
+        let mut bindings: Vec<(String, Expression)> = vec![];
+        let result = if is_free(target_wire) {
+            Expression::Wire(target_wire.to_string())
+        } else {
+            simplified.expand_wire_recursive(target_wire, &is_free, &shared_wires, &mut bindings)
+        };
+        SymbolicExpression { bindings, result }
+    }
+
+    /// Recursive support function used to expand the gate feeding the target wire into a symbolic
+    /// expression.
+    fn expand_wire_recursive(
+        &self,
+        target_wire: &str,
+        is_free: &dyn Fn(&str) -> bool,
+        shared_wires: &HashSet<&String>,
+        bindings: &mut Vec<(String, Expression)>,
+    ) -> Expression {
+        let mut term = |term: &String| {
+            if let Some(value) = literal_value(term, self.width) {
+                return Expression::Value(value);
+            }
+            if is_free(term) {
+                return Expression::Wire(term.to_string());
+            }
+            if !shared_wires.contains(term) {
+                return self.expand_wire_recursive(term, is_free, shared_wires, bindings);
+            }
+            // Shared sub-expression is bound once, after the bindings it depends on
+            if !bindings.iter().any(|(wire, _)| wire == term) {
+                let expression = self.expand_wire_recursive(term, is_free, shared_wires, bindings);
+                bindings.push((term.to_string(), expression));
+            }
+            Expression::Wire(term.to_string())
+        };
+        let binary =
+            |operator: BinaryOperator, left: Expression, right: Expression| Expression::Binary {
+                operator,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+        match &self.wire_ops[target_wire] {
+            Operation::Value { left } => term(left),
+            Operation::Not { left } => Expression::Not(Box::new(term(left))),
+            Operation::And { left, right } => binary(BinaryOperator::And, term(left), term(right)),
+            Operation::LShift { left, right } => {
+                binary(BinaryOperator::LShift, term(left), term(right))
+            }
+            Operation::RShift { left, right } => {
+                binary(BinaryOperator::RShift, term(left), term(right))
+            }
+            Operation::Or { left, right } => binary(BinaryOperator::Or, term(left), term(right)),
+            Operation::Xor { left, right } => binary(BinaryOperator::Xor, term(left), term(right)),
+            Operation::Nand { left, right } => {
+                binary(BinaryOperator::Nand, term(left), term(right))
+            }
+            Operation::Nor { left, right } => binary(BinaryOperator::Nor, term(left), term(right)),
+            Operation::Mux {
+                select,
+                left,
+                right,
+            } => Expression::Mux {
+                select: Box::new(term(select)),
+                left: Box::new(term(left)),
+                right: Box::new(term(right)),
+            },
+        }
+    }
+}
+
+impl fmt::Display for LogicCircuit {
+    /// Writes the circuit in the input syntax, with one gate per line in order of wire name.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut wires: Vec<&String> = self.wire_ops.keys().collect();
+        wires.sort();
+        for wire in wires {
+            writeln!(f, "{} -> {}", self.wire_ops[wire], wire)?;
+        }
+        Ok(())
+    }
+}
@@ -1,19 +1,75 @@
-use std::collections::HashMap;
+use std::slice;
 
-use super::Spell;
+use super::{Spell, SpellEffect};
 
-/// Represents an entity with magical abilities.
-#[derive(Clone)]
+/// Represents a spell effect that is currently active on a MagicEntity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct ActiveEffect {
+    spell_id: usize,
+    target: usize, // index of the target of the effect
+    turns: i64,    // number of turns remaining
+    per_turn: SpellEffect,
+    armour: i64,
+}
+
+/// Represents the result of processing an active spell effect for a turn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EffectTick {
+    spell_id: usize,
+    target: usize,
+    effect: SpellEffect,
+    timer: i64,          // number of turns remaining after the tick
+    armour_removed: i64, // armour removed if the effect wore off
+}
+
+impl EffectTick {
+    /// Gets the value of the "spell_id" field.
+    pub fn spell_id(&self) -> usize {
+        self.spell_id
+    }
+
+    /// Gets the value of the "target" field.
+    pub fn target(&self) -> usize {
+        self.target
+    }
+
+    /// Gets the value of the "effect" field.
+    pub fn effect(&self) -> SpellEffect {
+        self.effect
+    }
+
+    /// Gets the value of the "timer" field.
+    pub fn timer(&self) -> i64 {
+        self.timer
+    }
+
+    /// Gets the value of the "armour_removed" field.
+    pub fn armour_removed(&self) -> i64 {
+        self.armour_removed
+    }
+
+    /// Checks if the effect wore off after the tick.
+    pub fn has_worn_off(&self) -> bool {
+        self.timer <= 0
+    }
+}
+
+/// Represents an entity with magical abilities. Active spell effects are held in fixed-size slots
+/// (in the order they were started), so the entity can be copied and hashed cheaply.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct MagicEntity {
     health: i64,
     damage: i64,
     armour: i64,
     mana: i64,
     total_mana_spent: i64,
-    active_effects: HashMap<Spell, i64>,
+    active_effects: [Option<ActiveEffect>; MagicEntity::MAX_ACTIVE_EFFECTS],
 }
 
 impl MagicEntity {
+    /// Maximum number of spell effects that can be active on a MagicEntity at the same time.
+    pub const MAX_ACTIVE_EFFECTS: usize = 8;
+
     pub fn new(health: i64, damage: i64, armour: i64, mana: i64) -> MagicEntity {
         MagicEntity {
             health,
@@ -21,7 +77,7 @@ impl MagicEntity {
             armour,
             mana,
             total_mana_spent: 0,
-            active_effects: HashMap::new(),
+            active_effects: [None; MagicEntity::MAX_ACTIVE_EFFECTS],
         }
     }
 
@@ -50,6 +106,15 @@ impl MagicEntity {
         self.total_mana_spent
     }
 
+    /// Gets a copy of the MagicEntity with the total mana spent reset to 0, so that states reached
+    /// by spending different amounts of mana can be compared.
+    pub fn without_mana_spent(&self) -> MagicEntity {
+        MagicEntity {
+            total_mana_spent: 0,
+            ..*self
+        }
+    }
+
     /// Deals the specified amount of damage to the MagicEntity, ignoring the armour rating if
     /// required.
     pub fn deal_damage(&mut self, damage: i64, ignore_armour: bool) {
@@ -76,78 +141,165 @@ impl MagicEntity {
     }
 
     /// Checks if the MagicEntity has enough mana remaining to cast the specified spell.
-    pub fn can_cast(&self, spell: Spell) -> bool {
-        self.mana >= spell.mana()
+    pub fn can_cast(&self, spell: &Spell) -> bool {
+        self.mana >= spell.cost()
     }
 
     /// Checks if the specified spell effect is active.
-    pub fn is_effect_active(&self, spell: Spell) -> bool {
-        self.active_effects.contains_key(&spell)
+    pub fn is_effect_active(&self, spell: &Spell) -> bool {
+        self.effect_turns_remaining(spell) > 0
+    }
+
+    /// Gets the number of turns remaining for the specified spell effect (0 if not active).
+    pub fn effect_turns_remaining(&self, spell: &Spell) -> i64 {
+        self.active_effects
+            .iter()
+            .flatten()
+            .find(|effect| effect.spell_id == spell.id())
+            .map_or(0, |effect| effect.turns)
     }
 
-    /// Has the MagicEntity cast the specified spell and applies damage effects to the other
-    /// MagicEntity.
+    /// Has the MagicEntity cast the specified spell, applying its instant effect and starting its
+    /// effect over the following turns (if any).
     pub fn cast_spell(
         &mut self,
-        spell: Spell,
+        spell: &Spell,
         other: &mut MagicEntity,
         ignore_armour: bool,
+    ) -> Result<(), &str> {
+        self.cast_spell_at(spell, slice::from_mut(other), 0, ignore_armour)
+    }
+
+    /// Has the MagicEntity cast the specified spell at the target with the given index, applying
+    /// its instant effect and starting its effect over the following turns (if any). The effect
+    /// remains on the same target for its duration.
+    pub fn cast_spell_at(
+        &mut self,
+        spell: &Spell,
+        targets: &mut [MagicEntity],
+        target: usize,
+        ignore_armour: bool,
     ) -> Result<(), &str> {
         // Check if the MagicEntity can cast the spell
+        if target >= targets.len() {
+            return Err("Invalid spell target!");
+        }
         if !self.can_cast(spell) || self.is_effect_active(spell) {
             return Err("Cannot cast the spell!");
         }
         // Expend mana and cast spell
-        self.total_mana_spent += spell.mana();
-        self.mana -= spell.mana();
-        match spell {
-            Spell::MagicMissile => other.deal_damage(spell.damage(), ignore_armour),
-            Spell::Drain => {
-                other.deal_damage(spell.damage(), ignore_armour);
-                self.heal(spell.health());
-            }
-            Spell::Shield => {
-                self.active_effects.insert(spell, spell.effect_turns());
-                self.armour += spell.armour();
+        self.total_mana_spent += spell.cost();
+        self.mana -= spell.cost();
+        self.apply_effect(spell.instant(), &mut targets[target], ignore_armour);
+        self.start_effect_at(spell, target);
+        Ok(())
+    }
+
+    /// Starts the effect of the specified spell on the MagicEntity without casting the spell (so
+    /// no mana is spent and there is no instant effect). Any existing effect of the spell is
+    /// replaced. Spells without an effect lasting over the following turns are ignored.
+    ///
+    /// Panics if the effect would exceed the maximum number of active effects, which cannot happen
+    /// for spells from the same spellbook.
+    pub fn start_effect(&mut self, spell: &Spell) {
+        self.start_effect_at(spell, 0);
+    }
+
+    /// Starts the effect of the specified spell on the target with the given index.
+    fn start_effect_at(&mut self, spell: &Spell, target: usize) {
+        if !spell.has_effect() {
+            return;
+        }
+        // Remove any existing effect of the spell
+        for slot in self.active_effects.iter_mut() {
+            if slot.is_some_and(|effect| effect.spell_id == spell.id()) {
+                self.armour -= slot.take().unwrap().armour;
             }
-            Spell::Poison => _ = self.active_effects.insert(spell, spell.effect_turns()),
-            Spell::Recharge => _ = self.active_effects.insert(spell, spell.effect_turns()),
         }
-        Ok(())
+        self.compact_effects();
+        // Start the effect in the first free slot
+        let slot = self
+            .active_effects
+            .iter_mut()
+            .find(|slot| slot.is_none())
+            .expect("Maximum number of active effects exceeded!");
+        *slot = Some(ActiveEffect {
+            spell_id: spell.id(),
+            target,
+            turns: spell.duration(),
+            per_turn: spell.per_turn(),
+            armour: spell.armour(),
+        });
+        self.armour += spell.armour();
+    }
+
+    /// Reduces the mana of the MagicEntity to the given maximum, if it is above the maximum.
+    pub fn limit_mana(&mut self, max_mana: i64) {
+        self.mana = self.mana.min(max_mana);
     }
 
     /// Process the effects currently active on the MagicEntity and applies consequences to the
-    /// other MagicEntity.
-    pub fn process_effects(&mut self, other: &mut MagicEntity) {
-        let mut effects_to_remove: Vec<Spell> = vec![];
-        for (effect, turns) in self.active_effects.iter_mut() {
-            *turns -= 1;
-            match effect {
-                Spell::MagicMissile => (),
-                Spell::Drain => (),
-                Spell::Shield => {
-                    if *turns <= 0 {
-                        self.armour -= effect.armour();
-                        effects_to_remove.push(*effect);
-                    }
-                }
-                Spell::Poison => {
-                    other.deal_damage(effect.damage(), true);
-                    if *turns <= 0 {
-                        effects_to_remove.push(*effect);
-                    }
-                }
-                Spell::Recharge => {
-                    self.mana += effect.mana_heal();
-                    if *turns <= 0 {
-                        effects_to_remove.push(*effect);
-                    }
-                }
+    /// other MagicEntity. Effects giving armour are removed once they expire. Returns the result of
+    /// processing each effect, in the order the effects were started.
+    pub fn process_effects(&mut self, other: &mut MagicEntity) -> Vec<EffectTick> {
+        self.process_effects_on(slice::from_mut(other))
+    }
+
+    /// Process the effects currently active on the MagicEntity, applying consequences to the
+    /// target of each effect. Returns the result of processing each effect, in the order the
+    /// effects were started.
+    pub fn process_effects_on(&mut self, targets: &mut [MagicEntity]) -> Vec<EffectTick> {
+        let mut ticks: Vec<EffectTick> = vec![];
+        for i in 0..self.active_effects.len() {
+            let mut effect = match self.active_effects[i] {
+                Some(effect) => effect,
+                None => break,
+            };
+            effect.turns -= 1;
+            if let Some(other) = targets.get_mut(effect.target) {
+                self.apply_effect(effect.per_turn, other, true);
             }
+            let mut armour_removed = 0;
+            if effect.turns <= 0 {
+                // Remove effect that has expired
+                self.armour -= effect.armour;
+                armour_removed = effect.armour;
+                self.active_effects[i] = None;
+            } else {
+                self.active_effects[i] = Some(effect);
+            }
+            ticks.push(EffectTick {
+                spell_id: effect.spell_id,
+                target: effect.target,
+                effect: effect.per_turn,
+                timer: effect.turns,
+                armour_removed,
+            });
+        }
+        self.compact_effects();
+        ticks
+    }
+
+    /// Moves the active effects to the front of the effect slots, keeping them in the order they
+    /// were started.
+    fn compact_effects(&mut self) {
+        let mut active_effects = [None; MagicEntity::MAX_ACTIVE_EFFECTS];
+        for (slot, effect) in active_effects
+            .iter_mut()
+            .zip(self.active_effects.iter().flatten())
+        {
+            *slot = Some(*effect);
         }
-        // Remove effects that have expired
-        for effect in effects_to_remove {
-            self.active_effects.remove(&effect);
+        self.active_effects = active_effects;
+    }
+
+    /// Applies the spell effect, dealing damage to the other MagicEntity and restoring health and
+    /// mana to the MagicEntity.
+    fn apply_effect(&mut self, effect: SpellEffect, other: &mut MagicEntity, ignore_armour: bool) {
+        if effect.damage() > 0 {
+            other.deal_damage(effect.damage(), ignore_armour);
         }
+        self.heal(effect.heal());
+        self.mana += effect.mana();
     }
 }
@@ -1,19 +1,43 @@
+mod assembler;
+mod encounter;
+mod executiontrace;
 mod ingredient;
+mod loadoutanalysis;
+mod logiccircuit;
 mod magicentity;
+mod optimiser;
 mod passwordgenerator;
 mod present;
+mod recipe;
+mod recipeexplanation;
 mod reindeer;
+mod rpgduel;
 mod rpgentity;
 mod rpgitem;
+mod rpgshop;
 mod simplecomputer;
 mod spell;
+mod wizardfight;
 
+pub use assembler::{AssemblyError, AssemblyErrorKind};
+pub use encounter::{Encounter, EncounterState, Enemy, EnemyAction, EnemyAi, ScriptCondition};
+pub use executiontrace::{ExecutionTrace, TraceDivergence, TraceEntry};
 pub use ingredient::Ingredient;
-pub use magicentity::MagicEntity;
+pub use loadoutanalysis::{LoadoutAnalysis, LoadoutOutcome};
+pub use logiccircuit::{BinaryOperator, BitWidth, Expression, LogicCircuit, SymbolicExpression};
+pub use magicentity::{EffectTick, MagicEntity};
 pub use passwordgenerator::PasswordGenerator;
 pub use present::Present;
+pub use recipe::{LinearConstraint, Recipe, RecipeOptimiser, Relation};
+pub use recipeexplanation::{PropertySubtotal, RecipeExplanation, TeaspoonMove};
 pub use reindeer::Reindeer;
+pub use rpgduel::{simulate_duel, DuelEvent, DuelLog, Duelist};
 pub use rpgentity::RpgEntity;
 pub use rpgitem::RpgItem;
-pub use simplecomputer::SimpleComputer;
-pub use spell::Spell;
+pub use rpgshop::{Loadout, Shop, ShopCategory};
+pub use simplecomputer::{ExecutionOutcome, MachineState, SimpleComputer, StopReason};
+pub use spell::{Spell, SpellEffect, Spellbook};
+pub use wizardfight::{
+    play_round, simulate_encounter, simulate_fight, BattleLog, Combatant, Difficulty, FightEvent,
+    RoundOutcome,
+};
@@ -0,0 +1,156 @@
+use super::assembler::{Instruction, Operand};
+
+/// Represents the macro-instructions that replace common idioms in simple computer programs. Each
+/// macro-instruction has the same effect as interpreting the instructions it replaces.
+#[derive(Clone, Copy)]
+pub(super) enum MacroInstruction {
+    /// Run of "inc"/"dec" instructions on the same register.
+    AddConstant {
+        register: usize,
+        amount: isize,
+        length: usize,
+    },
+    /// Loop adding the value of the source register to the target register ("inc target",
+    /// "dec source", "jnz source -2", in either order of the first two instructions).
+    AddRegister { target: usize, source: usize },
+    /// Loop counting the number of Collatz steps taken for the register to reach 1, as used in the
+    /// AOC 2015 Day 23 program.
+    CollatzCount { register: usize, counter: usize },
+}
+
+impl MacroInstruction {
+    /// Applies the macro-instruction to the registers. Returns the offset to the next program
+    /// counter and number of instructions replaced by the macro-instruction, or None if the
+    /// registers do not meet the conditions for the macro-instruction to be used (in which case
+    /// the registers are unchanged).
+    pub(super) fn apply(&self, registers: &mut [isize]) -> Option<(isize, u64)> {
+        match *self {
+            MacroInstruction::AddConstant {
+                register,
+                amount,
+                length,
+            } => {
+                registers[register] = registers[register].checked_add(amount)?;
+                Some((length as isize, length as u64))
+            }
+            MacroInstruction::AddRegister { target, source } => {
+                // Loop only ends by counting the source register down to 0
+                let count = registers[source];
+                if count <= 0 {
+                    return None;
+                }
+                registers[target] = registers[target].checked_add(count)?;
+                registers[source] = 0;
+                Some((3, 3 * count as u64))
+            }
+            MacroInstruction::CollatzCount { register, counter } => {
+                // Loop only ends if the register reaches 1
+                let mut value = registers[register];
+                if value < 1 {
+                    return None;
+                }
+                let mut count = registers[counter];
+                let mut steps = 1; // final "jio" instruction
+                while value != 1 {
+                    count = count.checked_add(1)?;
+                    if value % 2 == 0 {
+                        value /= 2;
+                        steps += 5; // jio, inc, jie, hlf, jmp
+                    } else {
+                        value = value.checked_mul(3)?.checked_add(1)?;
+                        steps += 7; // jio, inc, jie, tpl, inc, jmp, jmp
+                    }
+                }
+                registers[register] = value;
+                registers[counter] = count;
+                Some((8, steps))
+            }
+        }
+    }
+}
+
+/// Finds the macro-instruction that can replace the instructions starting at each address in the
+/// program, if any.
+pub(super) fn find_macro_instructions(
+    instructions: &[Instruction],
+) -> Vec<Option<MacroInstruction>> {
+    (0..instructions.len())
+        .map(|address| {
+            let window = &instructions[address..];
+            match_collatz_count(window)
+                .or_else(|| match_add_register(window))
+                .or_else(|| match_add_constant(window))
+        })
+        .collect()
+}
+
+/// Matches a run of at least two "inc"/"dec" instructions on the same register.
+fn match_add_constant(window: &[Instruction]) -> Option<MacroInstruction> {
+    let change = |instruction: &Instruction| match instruction {
+        Instruction::Increment { register } => Some((*register, 1)),
+        Instruction::Decrement { register } => Some((*register, -1)),
+        _ => None,
+    };
+    let (register, _) = change(window.first()?)?;
+    let changes: Vec<isize> = window
+        .iter()
+        .map_while(|instruction| change(instruction).filter(|(r, _)| *r == register))
+        .map(|(_, amount)| amount)
+        .collect();
+    if changes.len() < 2 {
+        return None;
+    }
+    Some(MacroInstruction::AddConstant {
+        register,
+        amount: changes.iter().sum(),
+        length: changes.len(),
+    })
+}
+
+/// Matches the loop adding the value of one register to another register.
+fn match_add_register(window: &[Instruction]) -> Option<MacroInstruction> {
+    let (target, source) = match window.get(..3)? {
+        [Instruction::Increment { register: target }, Instruction::Decrement { register: source }, Instruction::JumpIfNotZero {
+            condition: Operand::Register(condition),
+            offset: -2,
+        }]
+        | [Instruction::Decrement { register: source }, Instruction::Increment { register: target }, Instruction::JumpIfNotZero {
+            condition: Operand::Register(condition),
+            offset: -2,
+        }] if source == condition => (*target, *source),
+        _ => return None,
+    };
+    if target == source {
+        return None;
+    }
+    Some(MacroInstruction::AddRegister { target, source })
+}
+
+/// Matches the Collatz step counting loop used in the AOC 2015 Day 23 program.
+fn match_collatz_count(window: &[Instruction]) -> Option<MacroInstruction> {
+    let (register, counter) = match window.get(..2)? {
+        [Instruction::JumpIfOne {
+            register,
+            offset: 8,
+        }, Instruction::Increment { register: counter }] => (*register, *counter),
+        _ => return None,
+    };
+    if register == counter {
+        return None;
+    }
+    let expected = [
+        Instruction::JumpIfEven {
+            register,
+            offset: 4,
+        },
+        Instruction::Triple { register },
+        Instruction::Increment { register },
+        Instruction::Jump { offset: 2 },
+        Instruction::Half { register },
+        Instruction::Jump { offset: -7 },
+    ];
+    if window.get(2..8)? != expected {
+        return None;
+    }
+    Some(MacroInstruction::CollatzCount { register, counter })
+}
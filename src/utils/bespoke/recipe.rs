@@ -0,0 +1,690 @@
+use super::{Ingredient, RecipeExplanation};
+
+/// Represents the relation required between the value of a linear expression and its bound.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Relation {
+    Equal,
+    AtMost,
+    AtLeast,
+}
+
+impl Relation {
+    /// Checks if the value meets the bound under the relation.
+    pub fn holds(&self, value: i64, bound: i64) -> bool {
+        match self {
+            Relation::Equal => value == bound,
+            Relation::AtMost => value <= bound,
+            Relation::AtLeast => value >= bound,
+        }
+    }
+
+    /// Checks if any value within the given range (inclusive) could meet the bound.
+    fn reachable(&self, min: i64, max: i64, bound: i64) -> bool {
+        match self {
+            Relation::Equal => min <= bound && bound <= max,
+            Relation::AtMost => min <= bound,
+            Relation::AtLeast => max >= bound,
+        }
+    }
+}
+
+/// Represents a linear constraint on the quantities of the ingredients in a recipe. The sum of each
+/// quantity multiplied by its coefficient must meet the bound under the relation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LinearConstraint {
+    coefficients: Vec<i64>, // one per ingredient
+    relation: Relation,
+    bound: i64,
+}
+
+impl LinearConstraint {
+    pub fn new(coefficients: &[i64], relation: Relation, bound: i64) -> LinearConstraint {
+        LinearConstraint {
+            coefficients: coefficients.to_vec(),
+            relation,
+            bound,
+        }
+    }
+
+    /// Gets the value of the "coefficients" field.
+    pub fn coefficients(&self) -> &[i64] {
+        &self.coefficients
+    }
+
+    /// Gets the value of the "relation" field.
+    pub fn relation(&self) -> Relation {
+        self.relation
+    }
+
+    /// Gets the value of the "bound" field.
+    pub fn bound(&self) -> i64 {
+        self.bound
+    }
+
+    /// Calculates the value of the linear expression for the given ingredient quantities.
+    pub fn value(&self, quantities: &[i64]) -> i64 {
+        self.coefficients
+            .iter()
+            .zip(quantities.iter())
+            .map(|(coefficient, quantity)| coefficient * quantity)
+            .sum()
+    }
+
+    /// Checks if the given ingredient quantities satisfy the constraint.
+    pub fn is_satisfied_by(&self, quantities: &[i64]) -> bool {
+        self.relation.holds(self.value(quantities), self.bound)
+    }
+}
+
+/// Represents a recipe found by the optimiser, giving the quantity of each ingredient (in the order
+/// the ingredients were given to the optimiser) and the score of the recipe.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Recipe {
+    quantities: Vec<i64>,
+    score: i64,
+}
+
+impl Recipe {
+    pub(super) fn new(quantities: &[i64], score: i64) -> Recipe {
+        Recipe {
+            quantities: quantities.to_vec(),
+            score,
+        }
+    }
+
+    /// Gets the value of the "quantities" field.
+    pub fn quantities(&self) -> &[i64] {
+        &self.quantities
+    }
+
+    /// Gets the value of the "score" field.
+    pub fn score(&self) -> i64 {
+        self.score
+    }
+}
+
+/// Optimiser used to find the highest-scoring recipe made from a fixed total amount of the given
+/// ingredients (AOC 2015 Day 15). The score of a recipe is the product of the totals of the scored
+/// properties, with each negative total counted as zero. Ingredients without a property are treated
+/// as having a value of zero for it. Recipes can be restricted by minimum and maximum amounts of
+/// each ingredient, and by arbitrary linear constraints on the ingredient quantities.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecipeOptimiser {
+    ingredients: Vec<Ingredient>,
+    scored: Vec<String>,
+    total: i64,
+    min_amounts: Vec<i64>,
+    max_amounts: Vec<i64>,
+    constraints: Vec<LinearConstraint>,
+}
+
+impl RecipeOptimiser {
+    /// Creates a new optimiser for recipes made from exactly the given total amount of the
+    /// ingredients, scored using the named properties. Returns an error if there are no
+    /// ingredients or scored properties, if the ingredient names are not unique, if the total is
+    /// negative, or if a scored property is not held by any ingredient.
+    pub fn new(
+        ingredients: &[Ingredient],
+        scored: &[&str],
+        total: i64,
+    ) -> Result<RecipeOptimiser, String> {
+        if ingredients.is_empty() {
+            return Err(String::from("Recipe has no ingredients"));
+        }
+        if scored.is_empty() {
+            return Err(String::from("Recipe has no scored properties"));
+        }
+        if total < 0 {
+            return Err(format!("Invalid recipe total: {}", total));
+        }
+        for (i, ingredient) in ingredients.iter().enumerate() {
+            if ingredients[..i]
+                .iter()
+                .any(|other| other.name() == ingredient.name())
+            {
+                return Err(format!("Duplicate ingredient name: {}", ingredient.name()));
+            }
+        }
+        let optimiser = RecipeOptimiser {
+            ingredients: ingredients.to_vec(),
+            scored: scored.iter().map(|property| property.to_string()).collect(),
+            total,
+            min_amounts: vec![0; ingredients.len()],
+            max_amounts: vec![total; ingredients.len()],
+            constraints: vec![],
+        };
+        for property in scored {
+            optimiser.property_coefficients(property)?;
+        }
+        Ok(optimiser)
+    }
+
+    /// Adds the constraint that the total of the named property across the recipe meets the value
+    /// under the relation (e.g., exactly 500 calories). Returns an error if the property is not
+    /// held by any ingredient.
+    pub fn with_property(
+        mut self,
+        property: &str,
+        relation: Relation,
+        value: i64,
+    ) -> Result<RecipeOptimiser, String> {
+        let coefficients = self.property_coefficients(property)?;
+        self.constraints
+            .push(LinearConstraint::new(&coefficients, relation, value));
+        Ok(self)
+    }
+
+    /// Restricts the amount of the named ingredient used in the recipe under the relation. Returns
+    /// an error if there is no ingredient with the name.
+    pub fn with_ingredient_bound(
+        mut self,
+        name: &str,
+        relation: Relation,
+        amount: i64,
+    ) -> Result<RecipeOptimiser, String> {
+        let i = match self.ingredients.iter().position(|ing| ing.name() == name) {
+            Some(i) => i,
+            None => return Err(format!("Unknown ingredient: {}", name)),
+        };
+        if relation != Relation::AtMost {
+            self.min_amounts[i] = self.min_amounts[i].max(amount);
+        }
+        if relation != Relation::AtLeast {
+            self.max_amounts[i] = self.max_amounts[i].min(amount);
+        }
+        Ok(self)
+    }
+
+    /// Adds the linear constraint on the ingredient quantities. Returns an error if the constraint
+    /// does not have one coefficient per ingredient.
+    pub fn with_constraint(
+        mut self,
+        constraint: LinearConstraint,
+    ) -> Result<RecipeOptimiser, String> {
+        if constraint.coefficients.len() != self.ingredients.len() {
+            return Err(format!(
+                "Constraint has {} coefficients for {} ingredients",
+                constraint.coefficients.len(),
+                self.ingredients.len()
+            ));
+        }
+        self.constraints.push(constraint);
+        Ok(self)
+    }
+
+    /// Gets the value of the "ingredients" field.
+    pub fn ingredients(&self) -> &[Ingredient] {
+        &self.ingredients
+    }
+
+    /// Gets the value of the "scored" field.
+    pub fn scored(&self) -> &[String] {
+        &self.scored
+    }
+
+    /// Gets the value of the "total" field.
+    pub fn total(&self) -> i64 {
+        self.total
+    }
+
+    /// Checks if the given ingredient quantities make a valid recipe, using exactly the total
+    /// amount of ingredients and meeting the ingredient bounds and constraints.
+    pub fn is_feasible(&self, quantities: &[i64]) -> bool {
+        quantities.len() == self.ingredients.len()
+            && quantities.iter().sum::<i64>() == self.total
+            && (0..quantities.len())
+                .all(|i| (self.min_amounts[i]..=self.max_amounts[i]).contains(&quantities[i]))
+            && self
+                .constraints
+                .iter()
+                .all(|constraint| constraint.is_satisfied_by(quantities))
+    }
+
+    /// Calculates the total of each scored property (before clamping negative totals to zero) for
+    /// the recipe with the given ingredient quantities, in the order the properties were given.
+    pub fn property_totals(&self, quantities: &[i64]) -> Vec<i64> {
+        self.scored
+            .iter()
+            .map(|property| {
+                self.ingredients
+                    .iter()
+                    .zip(quantities.iter())
+                    .map(|(ingredient, quantity)| {
+                        ingredient.property(property).unwrap_or(0) * quantity
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+
+    /// Calculates the score of the recipe with the given ingredient quantities. Scores saturate at
+    /// i64::MAX.
+    pub fn score(&self, quantities: &[i64]) -> i64 {
+        clamped_product(&self.property_totals(quantities))
+    }
+
+    /// Finds the highest-scoring recipe. Returned value is None if no recipe meets the
+    /// constraints. Ties are broken in favour of the recipe found first.
+    pub fn optimise(&self) -> Option<Recipe> {
+        self.top_recipes(1).pop()
+    }
+
+    /// Finds the given number of highest-scoring recipes, ranked from the highest score down.
+    /// Hill-climbing from an even split of the ingredients first gives a good recipe quickly,
+    /// which is then used to bound an exact branch-and-bound search over the ingredient quantities.
+    /// Fewer recipes are returned if fewer meet the constraints. Ties are broken in favour of the
+    /// recipe found first.
+    pub fn top_recipes(&self, count: usize) -> Vec<Recipe> {
+        if count == 0 {
+            return vec![];
+        }
+        let mut search = RecipeSearch::new(self, count);
+        if let Some(recipe) = self.hill_climb() {
+            search.add_recipe(recipe);
+        }
+        let mut quantities: Vec<i64> = vec![];
+        let mut property_totals = vec![0; self.scored.len()];
+        let mut constraint_totals = vec![0; self.constraints.len()];
+        search.branch(
+            self.total,
+            &mut quantities,
+            &mut property_totals,
+            &mut constraint_totals,
+        );
+        search.ranking
+    }
+
+    /// Finds the given number of highest-scoring recipes (see `top_recipes`), each explained by its
+    /// property subtotals and the effect of moving one teaspoon between its ingredients.
+    pub fn explore(&self, count: usize) -> Vec<RecipeExplanation> {
+        self.top_recipes(count)
+            .iter()
+            .map(|recipe| RecipeExplanation::new(self, recipe.quantities()))
+            .collect()
+    }
+
+    /// Gets the coefficients of the named property for each ingredient. Returns an error if the
+    /// property is not held by any ingredient.
+    fn property_coefficients(&self, property: &str) -> Result<Vec<i64>, String> {
+        let values: Vec<Option<i64>> = self
+            .ingredients
+            .iter()
+            .map(|ingredient| ingredient.property(property))
+            .collect();
+        if values.iter().all(|value| value.is_none()) {
+            return Err(format!("Unknown ingredient property: {}", property));
+        }
+        Ok(values.into_iter().map(|value| value.unwrap_or(0)).collect())
+    }
+
+    /// Spreads the total amount as evenly as possible across the ingredients, starting from the
+    /// minimum amount of each ingredient. Returned value is None if the split is not a valid
+    /// recipe.
+    fn even_split(&self) -> Option<Vec<i64>> {
+        let mut quantities = self.min_amounts.clone();
+        let mut remaining = self.total - quantities.iter().sum::<i64>();
+        while remaining > 0 {
+            let mut added = false;
+            for (i, quantity) in quantities.iter_mut().enumerate() {
+                if remaining > 0 && *quantity < self.max_amounts[i] {
+                    *quantity += 1;
+                    remaining -= 1;
+                    added = true;
+                }
+            }
+            if !added {
+                return None;
+            }
+        }
+        match self.is_feasible(&quantities) {
+            true => Some(quantities),
+            false => None,
+        }
+    }
+
+    /// Hill-climbs from the even split of the ingredients, repeatedly moving the single teaspoon
+    /// between two ingredients that most improves the score while keeping the recipe valid.
+    /// Returned value is None if the even split is not a valid recipe.
+    fn hill_climb(&self) -> Option<Recipe> {
+        let mut quantities = self.even_split()?;
+        let mut score = self.score(&quantities);
+        loop {
+            let mut best_move: Option<(usize, usize, i64)> = None;
+            for from in 0..quantities.len() {
+                for to in 0..quantities.len() {
+                    if from == to || quantities[from] <= self.min_amounts[from] {
+                        continue;
+                    }
+                    quantities[from] -= 1;
+                    quantities[to] += 1;
+                    if self.is_feasible(&quantities) {
+                        let new_score = self.score(&quantities);
+                        if new_score > best_move.map_or(score, |(_, _, best)| best) {
+                            best_move = Some((from, to, new_score));
+                        }
+                    }
+                    quantities[from] += 1;
+                    quantities[to] -= 1;
+                }
+            }
+            match best_move {
+                Some((from, to, new_score)) => {
+                    quantities[from] -= 1;
+                    quantities[to] += 1;
+                    score = new_score;
+                }
+                None => return Some(Recipe { quantities, score }),
+            }
+        }
+    }
+}
+
+/// Calculates the product of the given totals, with each negative total counted as zero. Product
+/// saturates at i64::MAX.
+fn clamped_product(totals: &[i64]) -> i64 {
+    totals.iter().fold(1, |product: i64, &total| {
+        product.saturating_mul(total.max(0))
+    })
+}
+
+/// Type definition for the amounts of (at most) two ingredients mixed to reach a linear programme
+/// optimum.
+type Mix = [(usize, f64); 2];
+
+/// Number of times the weights of the AM-GM bound are refined at each node of the search.
+const WEIGHT_REFINEMENTS: usize = 4;
+
+/// Represents the state of the branch-and-bound search for the highest-scoring recipes. The
+/// quantity of each ingredient is chosen in turn, with linear programme relaxations over the
+/// ingredients not yet chosen used to prune branches that cannot meet a constraint or enter the
+/// ranking.
+struct RecipeSearch<'a> {
+    optimiser: &'a RecipeOptimiser,
+    scored: Vec<Vec<i64>>,        // coefficients of each scored property
+    scored_values: Vec<Vec<f64>>, // coefficients of each scored property as floats for bounding
+    constraint_ranges: Vec<Vec<(i64, i64)>>, // min and max constraint coefficient from each index
+    min_remaining: Vec<i64>,      // total minimum amount of ingredients from each index
+    weights: Vec<f64>,            // weight of each scored property in the weighted sum bound
+    count: usize,                 // number of recipes to keep in the ranking
+    ranking: Vec<Recipe>,         // best recipes found so far, from the highest score down
+}
+
+impl RecipeSearch<'_> {
+    fn new(optimiser: &RecipeOptimiser, count: usize) -> RecipeSearch<'_> {
+        let n = optimiser.ingredients.len();
+        let scored: Vec<Vec<i64>> = optimiser
+            .scored
+            .iter()
+            .map(|property| optimiser.property_coefficients(property).unwrap())
+            .collect();
+        let suffix_range = |coefficients: &[i64], i: usize| {
+            let suffix = &coefficients[i..];
+            (
+                suffix.iter().copied().min().unwrap_or(0),
+                suffix.iter().copied().max().unwrap_or(0),
+            )
+        };
+        RecipeSearch {
+            optimiser,
+            scored_values: scored
+                .iter()
+                .map(|coefficients| coefficients.iter().map(|&c| c as f64).collect())
+                .collect(),
+            scored,
+            constraint_ranges: optimiser
+                .constraints
+                .iter()
+                .map(|c| (0..=n).map(|i| suffix_range(&c.coefficients, i)).collect())
+                .collect(),
+            min_remaining: (0..=n)
+                .map(|i| optimiser.min_amounts[i..].iter().sum())
+                .collect(),
+            weights: vec![],
+            count,
+            ranking: vec![],
+        }
+    }
+
+    /// Gets the score a recipe must beat to enter the ranking. Returned value is None if the
+    /// ranking is not yet full.
+    fn threshold(&self) -> Option<i64> {
+        match self.ranking.len() < self.count {
+            true => None,
+            false => self.ranking.last().map(|recipe| recipe.score),
+        }
+    }
+
+    /// Adds the recipe to the ranking if it beats the threshold and is not already ranked, keeping
+    /// recipes with equal scores in the order found. When the recipe becomes the highest-ranked,
+    /// each scored property is weighted by the inverse of its total in the recipe so the weighted
+    /// sum bound is tightest around it.
+    fn add_recipe(&mut self, recipe: Recipe) {
+        if self
+            .threshold()
+            .is_some_and(|threshold| recipe.score <= threshold)
+            || self
+                .ranking
+                .iter()
+                .any(|r| r.quantities == recipe.quantities)
+        {
+            return;
+        }
+        let position = self
+            .ranking
+            .iter()
+            .position(|r| r.score < recipe.score)
+            .unwrap_or(self.ranking.len());
+        if position == 0 {
+            self.weights = self
+                .optimiser
+                .property_totals(&recipe.quantities)
+                .iter()
+                .map(|&total| 1.0 / total.max(1) as f64)
+                .collect();
+        }
+        self.ranking.insert(position, recipe);
+        self.ranking.truncate(self.count);
+    }
+
+    /// Finds the greatest amount that the remaining ingredients from the given index could add to
+    /// the linear expression with the given value per teaspoon of each ingredient, along with the
+    /// amounts of the (at most two) ingredients giving the greatest amount. Each constraint is
+    /// relaxed to a linear programme over the remaining quantities, whose optimum uses at most two
+    /// ingredients, so the bound is the least of the optima for each constraint. Returned value is
+    /// None if a constraint cannot be met by the remaining ingredients.
+    fn max_addition(
+        &self,
+        values: &[f64],
+        i: usize,
+        remaining: i64,
+        constraint_totals: &[i64],
+    ) -> Option<(f64, Mix)> {
+        let n = self.optimiser.ingredients.len();
+        let amount_left = remaining as f64;
+        let mut bound = (f64::NEG_INFINITY, [(i, 0.0); 2]);
+        for (j, value) in values.iter().enumerate().skip(i) {
+            if value * amount_left > bound.0 {
+                bound = (value * amount_left, [(j, amount_left), (j, 0.0)]);
+            }
+        }
+        for (c, constraint) in self.optimiser.constraints.iter().enumerate() {
+            let coefficients = &constraint.coefficients;
+            let target = constraint.bound - constraint_totals[c];
+            let mut optimum = (f64::NEG_INFINITY, [(i, 0.0); 2]);
+            for j in i..n {
+                // Remaining amount given entirely to one ingredient
+                if constraint
+                    .relation
+                    .holds(coefficients[j] * remaining, target)
+                    && values[j] * amount_left > optimum.0
+                {
+                    optimum = (values[j] * amount_left, [(j, amount_left), (j, 0.0)]);
+                }
+                // Remaining amount split between two ingredients to meet the bound exactly
+                for k in j + 1..n {
+                    if coefficients[j] == coefficients[k] {
+                        continue;
+                    }
+                    let amount = (target as f64 - coefficients[k] as f64 * amount_left)
+                        / (coefficients[j] - coefficients[k]) as f64;
+                    let sum = values[j] * amount + values[k] * (amount_left - amount);
+                    if (0.0..=amount_left).contains(&amount) && sum > optimum.0 {
+                        optimum = (sum, [(j, amount), (k, amount_left - amount)]);
+                    }
+                }
+            }
+            if optimum.0 == f64::NEG_INFINITY {
+                return None;
+            }
+            if optimum.0 < bound.0 {
+                bound = optimum;
+            }
+        }
+        Some(bound)
+    }
+
+    /// Checks if the threshold of the ranking could be beaten by the ingredients remaining. The
+    /// product of the scored property totals is bounded by the product of the greatest total each
+    /// property could reach, and by the AM-GM inequality on the greatest weighted sum of the
+    /// totals. Weights start from the highest-ranked recipe, then are refined using the totals
+    /// giving the greatest weighted sum.
+    fn can_beat_threshold(
+        &self,
+        i: usize,
+        remaining: i64,
+        property_totals: &[i64],
+        constraint_totals: &[i64],
+    ) -> bool {
+        let target = match self.threshold() {
+            Some(threshold) => threshold as f64 + 1.0,
+            None => return true,
+        };
+        let mut product_bound = 1.0;
+        for (p, &total) in property_totals.iter().enumerate() {
+            match self.max_addition(&self.scored_values[p], i, remaining, constraint_totals) {
+                Some((added, _)) => product_bound *= (total as f64 + added).max(0.0),
+                None => return false,
+            }
+        }
+        // Scores are integers, so allow for rounding error when comparing with the threshold
+        if product_bound * (1.0 + 1e-9) < target {
+            return false;
+        }
+        let count = self.scored.len() as f64;
+        let mut weights = self.weights.clone();
+        for _ in 0..WEIGHT_REFINEMENTS {
+            let values: Vec<f64> = (0..self.optimiser.ingredients.len())
+                .map(|j| {
+                    (0..weights.len())
+                        .map(|p| weights[p] * self.scored_values[p][j])
+                        .sum()
+                })
+                .collect();
+            let (added, mix) = match self.max_addition(&values, i, remaining, constraint_totals) {
+                Some(result) => result,
+                None => return false,
+            };
+            let weighted_sum = (0..weights.len())
+                .map(|p| weights[p] * property_totals[p] as f64)
+                .sum::<f64>()
+                + added;
+            let weight_product: f64 = weights.iter().product();
+            let amgm_bound = (weighted_sum.max(0.0) / count).powf(count) / weight_product;
+            if amgm_bound * (1.0 + 1e-9) < target {
+                return false;
+            }
+            // Move the weights halfway towards those balancing the totals giving the greatest sum
+            for (p, weight) in weights.iter_mut().enumerate() {
+                let total = property_totals[p] as f64
+                    + mix
+                        .iter()
+                        .map(|&(j, amount)| self.scored_values[p][j] * amount)
+                        .sum::<f64>();
+                *weight = 2.0 / (1.0 / *weight + total.max(1.0));
+            }
+        }
+        true
+    }
+
+    /// Chooses the quantity of the next ingredient, given the amount of the total remaining and the
+    /// running totals of the scored properties and constraint expressions.
+    fn branch(
+        &mut self,
+        remaining: i64,
+        quantities: &mut Vec<i64>,
+        property_totals: &mut [i64],
+        constraint_totals: &mut [i64],
+    ) {
+        let i = quantities.len();
+        let n = self.optimiser.ingredients.len();
+        if i == n {
+            let score = clamped_product(property_totals);
+            if remaining == 0
+                && self.threshold().is_none_or(|threshold| score > threshold)
+                && self.meets_constraints(constraint_totals)
+            {
+                self.add_recipe(Recipe {
+                    quantities: quantities.clone(),
+                    score,
+                });
+            }
+            return;
+        }
+        // Prune if a constraint cannot be met by the ingredients remaining
+        for (c, constraint) in self.optimiser.constraints.iter().enumerate() {
+            let (min_coefficient, max_coefficient) = self.constraint_ranges[c][i];
+            let min = constraint_totals[c] + remaining * min_coefficient;
+            let max = constraint_totals[c] + remaining * max_coefficient;
+            if !constraint.relation.reachable(min, max, constraint.bound) {
+                return;
+            }
+        }
+        // Prune if the ranking cannot be entered with the ingredients remaining
+        if !self.can_beat_threshold(i, remaining, property_totals, constraint_totals) {
+            return;
+        }
+        let mut min_quantity = self.optimiser.min_amounts[i];
+        let max_quantity = self.optimiser.max_amounts[i].min(remaining - self.min_remaining[i + 1]);
+        if i == n - 1 {
+            // Last ingredient makes up the rest of the total
+            min_quantity = min_quantity.max(remaining);
+        }
+        for quantity in min_quantity..=max_quantity {
+            quantities.push(quantity);
+            self.add_ingredient(i, quantity, property_totals, constraint_totals);
+            self.branch(
+                remaining - quantity,
+                quantities,
+                property_totals,
+                constraint_totals,
+            );
+            self.add_ingredient(i, -quantity, property_totals, constraint_totals);
+            quantities.pop();
+        }
+    }
+
+    /// Adds the given quantity of the ingredient to the running totals.
+    fn add_ingredient(
+        &self,
+        i: usize,
+        quantity: i64,
+        property_totals: &mut [i64],
+        constraint_totals: &mut [i64],
+    ) {
+        for (p, total) in property_totals.iter_mut().enumerate() {
+            *total += self.scored[p][i] * quantity;
+        }
+        for (c, total) in constraint_totals.iter_mut().enumerate() {
+            *total += self.optimiser.constraints[c].coefficients[i] * quantity;
+        }
+    }
+
+    /// Checks if the running totals of the constraint expressions meet every constraint.
+    fn meets_constraints(&self, constraint_totals: &[i64]) -> bool {
+        self.optimiser
+            .constraints
+            .iter()
+            .zip(constraint_totals.iter())
+            .all(|(constraint, &total)| constraint.relation.holds(total, constraint.bound))
+    }
+}
@@ -0,0 +1,170 @@
+use super::{Recipe, RecipeOptimiser};
+
+/// Represents the total of a single scored property across a recipe.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PropertySubtotal {
+    property: String,
+    total: i64, // total before clamping negative totals to zero
+}
+
+impl PropertySubtotal {
+    /// Gets the value of the "property" field.
+    pub fn property(&self) -> &str {
+        &self.property
+    }
+
+    /// Gets the value of the "total" field.
+    pub fn total(&self) -> i64 {
+        self.total
+    }
+
+    /// Checks if the total is clamped to zero when calculating the score of the recipe, which
+    /// makes the score zero.
+    pub fn is_clamped(&self) -> bool {
+        self.total < 0
+    }
+
+    /// Gets the factor contributed by the property to the score of the recipe.
+    pub fn factor(&self) -> i64 {
+        self.total.max(0)
+    }
+}
+
+/// Represents the effect on the score of a recipe of moving one teaspoon from one ingredient to
+/// another (given by their indices in the ingredients of the optimiser).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TeaspoonMove {
+    from: usize,
+    to: usize,
+    score: i64,        // score of the recipe after the move
+    change: i64,       // change in score from the original recipe
+    is_feasible: bool, // recipe after the move still meets the constraints
+}
+
+impl TeaspoonMove {
+    /// Gets the value of the "from" field.
+    pub fn from(&self) -> usize {
+        self.from
+    }
+
+    /// Gets the value of the "to" field.
+    pub fn to(&self) -> usize {
+        self.to
+    }
+
+    /// Gets the value of the "score" field.
+    pub fn score(&self) -> i64 {
+        self.score
+    }
+
+    /// Gets the value of the "change" field.
+    pub fn change(&self) -> i64 {
+        self.change
+    }
+
+    /// Gets the value of the "is_feasible" field.
+    pub fn is_feasible(&self) -> bool {
+        self.is_feasible
+    }
+}
+
+/// Represents the explanation of the score of a recipe, giving the subtotal of each scored property
+/// and the sensitivity of the score to moving one teaspoon between each pair of ingredients.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecipeExplanation {
+    recipe: Recipe,
+    subtotals: Vec<PropertySubtotal>,
+    moves: Vec<TeaspoonMove>,
+}
+
+impl RecipeExplanation {
+    /// Explains the recipe with the given ingredient quantities, scored by the optimiser. Moves are
+    /// given for every ordered pair of different ingredients where the first ingredient is used in
+    /// the recipe.
+    pub fn new(optimiser: &RecipeOptimiser, quantities: &[i64]) -> RecipeExplanation {
+        let score = optimiser.score(quantities);
+        let subtotals = optimiser
+            .scored()
+            .iter()
+            .zip(optimiser.property_totals(quantities))
+            .map(|(property, total)| PropertySubtotal {
+                property: property.to_string(),
+                total,
+            })
+            .collect();
+        let mut moves: Vec<TeaspoonMove> = vec![];
+        let mut moved = quantities.to_vec();
+        for from in 0..quantities.len() {
+            if quantities[from] <= 0 {
+                continue;
+            }
+            for to in (0..quantities.len()).filter(|&to| to != from) {
+                moved[from] -= 1;
+                moved[to] += 1;
+                let new_score = optimiser.score(&moved);
+                moves.push(TeaspoonMove {
+                    from,
+                    to,
+                    score: new_score,
+                    change: new_score - score,
+                    is_feasible: optimiser.is_feasible(&moved),
+                });
+                moved[from] += 1;
+                moved[to] -= 1;
+            }
+        }
+        RecipeExplanation {
+            recipe: Recipe::new(quantities, score),
+            subtotals,
+            moves,
+        }
+    }
+
+    /// Gets the value of the "recipe" field.
+    pub fn recipe(&self) -> &Recipe {
+        &self.recipe
+    }
+
+    /// Gets the value of the "subtotals" field.
+    pub fn subtotals(&self) -> &[PropertySubtotal] {
+        &self.subtotals
+    }
+
+    /// Gets the value of the "moves" field.
+    pub fn moves(&self) -> &[TeaspoonMove] {
+        &self.moves
+    }
+
+    /// Gets the names of the scored properties whose totals are clamped to zero.
+    pub fn clamped_properties(&self) -> Vec<&str> {
+        self.subtotals
+            .iter()
+            .filter(|subtotal| subtotal.is_clamped())
+            .map(|subtotal| subtotal.property())
+            .collect()
+    }
+
+    /// Gets the feasible move giving the greatest increase in score (or least decrease, if no move
+    /// improves the score). Ties are broken in favour of the move listed first. Returned value is
+    /// None if there are no feasible moves.
+    pub fn best_move(&self) -> Option<&TeaspoonMove> {
+        self.feasible_moves()
+            .rev()
+            .max_by_key(|teaspoon_move| teaspoon_move.change)
+    }
+
+    /// Gets the feasible move giving the greatest decrease in score (or least increase, if every
+    /// move improves the score). Ties are broken in favour of the move listed first. Returned value
+    /// is None if there are no feasible moves.
+    pub fn worst_move(&self) -> Option<&TeaspoonMove> {
+        self.feasible_moves()
+            .min_by_key(|teaspoon_move| teaspoon_move.change)
+    }
+
+    /// Gets the moves that keep the recipe within the constraints of the optimiser.
+    fn feasible_moves(&self) -> impl DoubleEndedIterator<Item = &TeaspoonMove> {
+        self.moves
+            .iter()
+            .filter(|teaspoon_move| teaspoon_move.is_feasible)
+    }
+}
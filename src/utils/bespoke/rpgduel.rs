@@ -0,0 +1,164 @@
+use std::fmt;
+
+use super::RpgEntity;
+
+/// Represents the two sides of a duel in RPG Simulator 20XX.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Duelist {
+    Player,
+    Boss,
+}
+
+impl Duelist {
+    /// Gets the other side of the duel.
+    pub fn opponent(&self) -> Duelist {
+        match self {
+            Duelist::Player => Duelist::Boss,
+            Duelist::Boss => Duelist::Player,
+        }
+    }
+}
+
+impl fmt::Display for Duelist {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Duelist::Player => write!(f, "player"),
+            Duelist::Boss => write!(f, "boss"),
+        }
+    }
+}
+
+/// Represents the events that occur during a duel in RPG Simulator 20XX. Health values are given
+/// after the event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DuelEvent {
+    Regenerate {
+        duelist: Duelist,
+        healed: i64,
+        health: i64,
+    },
+    Attack {
+        attacker: Duelist,
+        damage: i64,
+        armour: i64,
+        dealt: i64,
+        health: i64, // health of the defender
+    },
+    Victory {
+        winner: Duelist,
+    },
+}
+
+/// Represents the log of events from a simulated duel in RPG Simulator 20XX, along with the number
+/// of turns taken and the winner (None if the duel reached the turn limit).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DuelLog {
+    events: Vec<DuelEvent>,
+    turns: u64,
+    winner: Option<Duelist>,
+}
+
+impl DuelLog {
+    /// Gets the value of the "events" field.
+    pub fn events(&self) -> &[DuelEvent] {
+        &self.events
+    }
+
+    /// Gets the value of the "turns" field.
+    pub fn turns(&self) -> u64 {
+        self.turns
+    }
+
+    /// Gets the value of the "winner" field.
+    pub fn winner(&self) -> Option<Duelist> {
+        self.winner
+    }
+
+    /// Converts the log into the narrative style used in the problem description, with one line
+    /// per event.
+    pub fn to_narrative(&self) -> String {
+        let mut output = String::new();
+        for event in self.events.iter() {
+            let line = match *event {
+                DuelEvent::Regenerate {
+                    duelist,
+                    healed,
+                    health,
+                } => format!(
+                    "- The {} regenerates {} hit points, up to {} hit points.",
+                    duelist, healed, health
+                ),
+                DuelEvent::Attack {
+                    attacker,
+                    damage,
+                    armour,
+                    dealt,
+                    health,
+                } => format!(
+                    "- The {} deals {}-{} = {} damage; the {} goes down to {} hit points.",
+                    attacker,
+                    damage,
+                    armour,
+                    dealt,
+                    attacker.opponent(),
+                    health
+                ),
+                DuelEvent::Victory { winner } => format!("The {} wins.", winner),
+            };
+            output.push_str(&line);
+            output.push('\n');
+        }
+        output
+    }
+}
+
+/// Simulates the duel between the player and the boss round by round, until either side is
+/// defeated or the turn limit is reached. The player attacks first unless only the boss strikes
+/// first. Each side regenerates health at the start of its own turn before attacking.
+pub fn simulate_duel(player: &RpgEntity, boss: &RpgEntity, max_turns: u64) -> DuelLog {
+    let mut player = *player;
+    let mut boss = *boss;
+    let mut attacker = match player.strikes_before(&boss) {
+        true => Duelist::Player,
+        false => Duelist::Boss,
+    };
+    let mut events: Vec<DuelEvent> = vec![];
+    let mut turns = 0;
+    while turns < max_turns {
+        turns += 1;
+        let (current, other) = match attacker {
+            Duelist::Player => (&mut player, &mut boss),
+            Duelist::Boss => (&mut boss, &mut player),
+        };
+        let healed = current.regenerate();
+        if healed > 0 {
+            events.push(DuelEvent::Regenerate {
+                duelist: attacker,
+                healed,
+                health: current.health(),
+            });
+        }
+        let dealt = current.attack(other);
+        events.push(DuelEvent::Attack {
+            attacker,
+            damage: current.damage(),
+            armour: other.armour(),
+            dealt,
+            health: other.health(),
+        });
+        if other.is_dead() {
+            events.push(DuelEvent::Victory { winner: attacker });
+            return DuelLog {
+                events,
+                turns,
+                winner: Some(attacker),
+            };
+        }
+        attacker = attacker.opponent();
+    }
+    DuelLog {
+        events,
+        turns,
+        winner: None,
+    }
+}
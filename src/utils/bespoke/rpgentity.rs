@@ -2,29 +2,79 @@ use std::cmp;
 
 /// Represents a single entity (player or enemy) used in RPG Simulator 20XX from AOC 2015 Day 21
 /// (https://adventofcode.com/2015/day/21).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct RpgEntity {
     health: i64,
+    max_health: i64, // health cannot be regenerated above the starting health
     damage: i64,
     armour: i64,
+    regen: i64,         // health regenerated at the start of each of the entity's turns
+    first_strike: bool, // entity attacks first in a duel
 }
 
 impl RpgEntity {
     pub fn new(health: i64, damage: i64, armour: i64) -> RpgEntity {
         RpgEntity {
             health,
+            max_health: health,
             damage,
             armour,
+            regen: 0,
+            first_strike: false,
         }
     }
 
+    /// Sets the health regenerated by the entity at the start of each of its turns.
+    pub fn with_regen(mut self, regen: i64) -> RpgEntity {
+        self.regen = regen;
+        self
+    }
+
+    /// Sets the entity to attack first in a duel (unless the other entity also strikes first).
+    pub fn with_first_strike(mut self) -> RpgEntity {
+        self.first_strike = true;
+        self
+    }
+
+    /// Gets the value of the "health" field.
+    pub fn health(&self) -> i64 {
+        self.health
+    }
+
+    /// Gets the value of the "max_health" field.
+    pub fn max_health(&self) -> i64 {
+        self.max_health
+    }
+
+    /// Gets the value of the "damage" field.
+    pub fn damage(&self) -> i64 {
+        self.damage
+    }
+
+    /// Gets the value of the "armour" field.
+    pub fn armour(&self) -> i64 {
+        self.armour
+    }
+
+    /// Gets the value of the "regen" field.
+    pub fn regen(&self) -> i64 {
+        self.regen
+    }
+
+    /// Gets the value of the "first_strike" field.
+    pub fn has_first_strike(&self) -> bool {
+        self.first_strike
+    }
+
     /// Checks if the entity is dead (non-positive health points remaining).
     pub fn is_dead(&self) -> bool {
         self.health <= 0
     }
 
-    /// Determines the number of turns required for the entity to defeat the other entity.
+    /// Determines the number of turns required for the entity to defeat the other entity. Health
+    /// regenerated by the other entity is not taken into account.
     pub fn turns_to_defeat(&self, other: &RpgEntity) -> u64 {
-        let turn_damage = cmp::max(self.damage - other.armour, 1);
+        let turn_damage = self.attack_damage(other);
         let turns = {
             let temp = other.health / turn_damage;
             if other.health % turn_damage > 0 {
@@ -35,4 +85,46 @@ impl RpgEntity {
         };
         turns as u64
     }
+
+    /// Checks if the entity would defeat the other entity in a duel, using the number of turns
+    /// each needs to defeat the other. The entity attacks first unless only the other entity
+    /// strikes first. Health regenerated by either entity is not taken into account.
+    pub fn defeats(&self, other: &RpgEntity) -> bool {
+        let turns = self.turns_to_defeat(other);
+        let other_turns = other.turns_to_defeat(self);
+        match self.strikes_before(other) {
+            true => turns <= other_turns,
+            false => turns < other_turns,
+        }
+    }
+
+    /// Checks if the entity attacks before the other entity in a duel. The entity attacks first
+    /// unless only the other entity strikes first.
+    pub fn strikes_before(&self, other: &RpgEntity) -> bool {
+        self.first_strike || !other.first_strike
+    }
+
+    /// Has the entity attack the other entity, dealing its damage less the armour of the other
+    /// entity (at least 1). Returns the amount of damage dealt.
+    pub fn attack(&self, other: &mut RpgEntity) -> i64 {
+        let damage = self.attack_damage(other);
+        other.health -= damage;
+        damage
+    }
+
+    /// Regenerates the health of the entity, up to its starting health. Dead entities do not
+    /// regenerate. Returns the amount of health regenerated.
+    pub fn regenerate(&mut self) -> i64 {
+        if self.is_dead() {
+            return 0;
+        }
+        let healed = cmp::max(cmp::min(self.regen, self.max_health - self.health), 0);
+        self.health += healed;
+        healed
+    }
+
+    /// Determines the damage dealt by each attack from the entity on the other entity.
+    fn attack_damage(&self, other: &RpgEntity) -> i64 {
+        cmp::max(self.damage - other.armour, 1)
+    }
 }
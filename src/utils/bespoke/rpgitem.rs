@@ -1,20 +1,36 @@
 /// Represents a single item used in RPG Simulator 20XX from AOC 2015 Day 21
 /// (https://adventofcode.com/2015/day/21).
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct RpgItem {
+    name: String,
     cost: i64,
     damage: i64,
     armour: i64,
+    regen: i64, // health regenerated by the holder at the start of each of their turns
 }
 
 impl RpgItem {
-    pub fn new(cost: i64, damage: i64, armour: i64) -> RpgItem {
+    pub fn new(name: &str, cost: i64, damage: i64, armour: i64) -> RpgItem {
         RpgItem {
+            name: name.to_string(),
             cost,
             damage,
             armour,
+            regen: 0,
         }
     }
 
+    /// Sets the health regenerated by the holder of the item at the start of each of their turns.
+    pub fn with_regen(mut self, regen: i64) -> RpgItem {
+        self.regen = regen;
+        self
+    }
+
+    /// Gets the value of the "name" field.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     /// Gets the value of the "cost" field.
     pub fn cost(&self) -> i64 {
         self.cost
@@ -29,4 +45,9 @@ impl RpgItem {
     pub fn armour(&self) -> i64 {
         self.armour
     }
+
+    /// Gets the value of the "regen" field.
+    pub fn regen(&self) -> i64 {
+        self.regen
+    }
 }
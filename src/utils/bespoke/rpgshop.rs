@@ -0,0 +1,200 @@
+use fancy_regex::Regex;
+use itertools::Itertools;
+use lazy_static::lazy_static;
+
+use super::RpgItem;
+
+lazy_static! {
+    static ref REGEX_CATEGORY: Regex =
+        Regex::new(r"^(\S.*?) \[(\d+)-(\d+)\]:\s+Cost\s+Damage\s+Armor(\s+Regen)?$").unwrap();
+    static ref REGEX_ITEM: Regex = Regex::new(r"^(\S.*?)\s+(\d+)\s+(\d+)\s+(\d+)$").unwrap();
+    static ref REGEX_ITEM_REGEN: Regex =
+        Regex::new(r"^(\S.*?)\s+(\d+)\s+(\d+)\s+(\d+)\s+(\d+)$").unwrap();
+}
+
+/// Represents a category of items held by the shop in RPG Simulator 20XX, along with the minimum
+/// and maximum number of items from the category that can be held at once.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShopCategory {
+    name: String,
+    min_slots: usize,
+    max_slots: usize,
+    items: Vec<RpgItem>,
+}
+
+impl ShopCategory {
+    pub fn new(name: &str, min_slots: usize, max_slots: usize, items: &[RpgItem]) -> ShopCategory {
+        ShopCategory {
+            name: name.to_string(),
+            min_slots,
+            max_slots,
+            items: items.to_vec(),
+        }
+    }
+
+    /// Gets the value of the "name" field.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the value of the "min_slots" field.
+    pub fn min_slots(&self) -> usize {
+        self.min_slots
+    }
+
+    /// Gets the value of the "max_slots" field.
+    pub fn max_slots(&self) -> usize {
+        self.max_slots
+    }
+
+    /// Gets the value of the "items" field.
+    pub fn items(&self) -> &[RpgItem] {
+        &self.items
+    }
+
+    /// Gets each of the possible selections of items from the category that fill between the
+    /// minimum and maximum number of slots (with no item selected more than once).
+    fn selections(&self) -> Vec<Vec<&RpgItem>> {
+        (self.min_slots..=self.max_slots.min(self.items.len()))
+            .flat_map(|slots| self.items.iter().combinations(slots))
+            .collect()
+    }
+}
+
+/// Represents the set of items bought from the shop and held by the player.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Loadout {
+    items: Vec<RpgItem>,
+}
+
+impl Loadout {
+    pub fn new(items: &[RpgItem]) -> Loadout {
+        Loadout {
+            items: items.to_vec(),
+        }
+    }
+
+    /// Gets the value of the "items" field.
+    pub fn items(&self) -> &[RpgItem] {
+        &self.items
+    }
+
+    /// Gets the total cost of the items in the loadout.
+    pub fn cost(&self) -> i64 {
+        self.items.iter().map(|item| item.cost()).sum()
+    }
+
+    /// Gets the total damage given by the items in the loadout.
+    pub fn damage(&self) -> i64 {
+        self.items.iter().map(|item| item.damage()).sum()
+    }
+
+    /// Gets the total armour given by the items in the loadout.
+    pub fn armour(&self) -> i64 {
+        self.items.iter().map(|item| item.armour()).sum()
+    }
+
+    /// Gets the total health regenerated per turn by the items in the loadout.
+    pub fn regen(&self) -> i64 {
+        self.items.iter().map(|item| item.regen()).sum()
+    }
+}
+
+/// Represents the shop in RPG Simulator 20XX from AOC 2015 Day 21
+/// (https://adventofcode.com/2015/day/21), with its items split into named categories.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Shop {
+    categories: Vec<ShopCategory>,
+}
+
+impl Shop {
+    /// Creates a new shop with the given item categories. Returns an error if there are no
+    /// categories, if the names of the categories or items are not unique, or if a category cannot
+    /// fill its minimum number of slots.
+    pub fn new(categories: &[ShopCategory]) -> Result<Shop, String> {
+        if categories.is_empty() {
+            return Err(String::from("Shop has no item categories"));
+        }
+        for (i, category) in categories.iter().enumerate() {
+            if categories[..i]
+                .iter()
+                .any(|other| other.name == category.name)
+            {
+                return Err(format!("Duplicate category name: {}", category.name));
+            }
+            if category.min_slots > category.max_slots || category.min_slots > category.items.len()
+            {
+                return Err(format!("Invalid slot counts: {}", category.name));
+            }
+        }
+        let items: Vec<&RpgItem> = categories.iter().flat_map(|c| c.items.iter()).collect();
+        for (i, item) in items.iter().enumerate() {
+            if items[..i].iter().any(|other| other.name() == item.name()) {
+                return Err(format!("Duplicate item name: {}", item.name()));
+            }
+        }
+        Ok(Shop {
+            categories: categories.to_vec(),
+        })
+    }
+
+    /// Parses the shop from the listing format used in the problem description. Each category
+    /// starts with a header line giving its name and slot counts (e.g., "Rings [0-2]: Cost Damage
+    /// Armor"), followed by one line per item giving its name, cost, damage and armour. Categories
+    /// with a "Regen" column after "Armor" also give the health regenerated by each item per turn.
+    /// Categories are separated by blank lines.
+    pub fn parse(raw_input: &str) -> Result<Shop, String> {
+        let mut categories: Vec<ShopCategory> = vec![];
+        let mut has_regen = false;
+        for line in raw_input.lines().map(|line| line.trim()) {
+            if line.is_empty() {
+                continue;
+            }
+            let item_regex: &Regex = match has_regen {
+                true => &REGEX_ITEM_REGEN,
+                false => &REGEX_ITEM,
+            };
+            if let Ok(Some(caps)) = REGEX_CATEGORY.captures(line) {
+                let min_slots = caps[2].parse::<usize>().map_err(|e| e.to_string())?;
+                let max_slots = caps[3].parse::<usize>().map_err(|e| e.to_string())?;
+                categories.push(ShopCategory::new(&caps[1], min_slots, max_slots, &[]));
+                has_regen = caps.get(4).is_some();
+            } else if let Ok(Some(caps)) = item_regex.captures(line) {
+                let category = match categories.last_mut() {
+                    Some(category) => category,
+                    None => return Err(format!("Item listed before any category: {}", line)),
+                };
+                let values = (2..caps.len())
+                    .map(|i| caps[i].parse::<i64>().map_err(|e| e.to_string()))
+                    .collect::<Result<Vec<i64>, String>>()?;
+                let mut item = RpgItem::new(&caps[1], values[0], values[1], values[2]);
+                if let Some(&regen) = values.get(3) {
+                    item = item.with_regen(regen);
+                }
+                category.items.push(item);
+            } else {
+                return Err(format!("Invalid shop listing line: {}", line));
+            }
+        }
+        Shop::new(&categories)
+    }
+
+    /// Gets the value of the "categories" field.
+    pub fn categories(&self) -> &[ShopCategory] {
+        &self.categories
+    }
+
+    /// Gets each of the loadouts that can be bought from the shop while honouring the slot counts
+    /// of every category. Items in each loadout are given in the order they are listed in the shop.
+    pub fn loadouts(&self) -> Vec<Loadout> {
+        self.categories
+            .iter()
+            .map(|category| category.selections())
+            .multi_cartesian_product()
+            .map(|selections| {
+                let items: Vec<RpgItem> = selections.into_iter().flatten().cloned().collect();
+                Loadout { items }
+            })
+            .collect()
+    }
+}
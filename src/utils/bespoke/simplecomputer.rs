@@ -1,157 +1,499 @@
-use fancy_regex::Regex;
-use lazy_static::lazy_static;
-
-lazy_static! {
-    static ref REGEX_HLF: Regex = Regex::new(r"^hlf (a|b)$").unwrap();
-    static ref REGEX_TPL: Regex = Regex::new(r"^tpl (a|b)$").unwrap();
-    static ref REGEX_INC: Regex = Regex::new(r"^inc (a|b)$").unwrap();
-    static ref REGEX_JMP: Regex = Regex::new(r"^jmp ([-+]?\d+)$").unwrap();
-    static ref REGEX_JIE: Regex = Regex::new(r"^jie (a|b), ([-+]?\d+)$").unwrap();
-    static ref REGEX_JIO: Regex = Regex::new(r"^jio (a|b), ([-+]?\d+)$").unwrap();
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use super::assembler::{assemble, disassemble, Instruction, Operand};
+use super::optimiser::find_macro_instructions;
+use super::{AssemblyError, ExecutionTrace, TraceEntry};
+
+/// Represents the reasons for the simple computer pausing execution when being debugged.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    Halted,
+    Breakpoint {
+        pc: isize,
+    },
+    Watchpoint {
+        register: String,
+        old_value: isize,
+        new_value: isize,
+    },
+}
+
+/// Represents the outcome of executing the simple computer with a step budget. Each outcome holds
+/// the total number of instructions executed by the computer and its final register values.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExecutionOutcome {
+    Halted {
+        steps: u64,
+        registers: Vec<isize>,
+    },
+    StepLimit {
+        steps: u64,
+        registers: Vec<isize>,
+    },
+    LoopDetected {
+        steps: u64,
+        registers: Vec<isize>,
+        loop_start: u64, // step count when the repeated state was first reached
+    },
+}
+
+impl ExecutionOutcome {
+    /// Gets the total number of instructions executed by the simple computer.
+    pub fn steps(&self) -> u64 {
+        match self {
+            ExecutionOutcome::Halted { steps, .. }
+            | ExecutionOutcome::StepLimit { steps, .. }
+            | ExecutionOutcome::LoopDetected { steps, .. } => *steps,
+        }
+    }
+
+    /// Gets the final register values of the simple computer.
+    pub fn registers(&self) -> &[isize] {
+        match self {
+            ExecutionOutcome::Halted { registers, .. }
+            | ExecutionOutcome::StepLimit { registers, .. }
+            | ExecutionOutcome::LoopDetected { registers, .. } => registers,
+        }
+    }
 }
 
-/// Represents the different instructions that can be executed by the simple computer.
-#[derive(Clone, Copy)]
-enum Instruction {
-    Half { register: char },                      // hlf
-    Triple { register: char },                    // tpl
-    Increment { register: char },                 // inc
-    Jump { offset: isize },                       // jmp
-    JumpIfEven { register: char, offset: isize }, // jie
-    JumpIfOne { register: char, offset: isize },  // jio
+/// Represents a snapshot of the full machine state of the simple computer, which can be serialised
+/// to save a long run or share a bug reproduction. The program is held in its input syntax, with
+/// one instruction per line.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MachineState {
+    program: Vec<String>,
+    pc: isize,
+    register_names: Vec<String>,
+    registers: Vec<isize>,
+    output: Vec<isize>,
+    steps: u64,
+}
+
+impl MachineState {
+    /// Gets the value of the "program" field.
+    pub fn program(&self) -> &[String] {
+        &self.program
+    }
+
+    /// Gets the value of the "pc" field.
+    pub fn pc(&self) -> isize {
+        self.pc
+    }
+
+    /// Gets the value of the "register_names" field.
+    pub fn register_names(&self) -> &[String] {
+        &self.register_names
+    }
+
+    /// Gets the value of the "registers" field.
+    pub fn registers(&self) -> &[isize] {
+        &self.registers
+    }
+
+    /// Gets the value of the "output" field.
+    pub fn output(&self) -> &[isize] {
+        &self.output
+    }
+
+    /// Gets the value of the "steps" field.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
 }
 
-/// Represents the simple computer used in AOC 2015 Day 23 (https://adventofcode.com/2015/day/23).
+/// Represents the simple computer used in AOC 2015 Day 23 (https://adventofcode.com/2015/day/23),
+/// extended with a configurable register file and the assembunny-style "cpy", "dec", "jnz" and
+/// "out" instructions.
 #[derive(Clone)]
 pub struct SimpleComputer {
     instructions: Vec<Instruction>,
     pc: isize, // program counter
-    register_a: isize,
-    register_b: isize,
+    register_names: Vec<String>,
+    registers: Vec<isize>,
+    output: Vec<isize>, // values given by "out" instructions
+    steps: u64,         // number of instructions executed
+    breakpoints: BTreeSet<isize>,
+    watchpoints: BTreeSet<usize>,
+    trace: Option<ExecutionTrace>, // only recorded if tracing is enabled
 }
 
 impl SimpleComputer {
-    pub fn new(raw_input: &str, register_a: isize, register_b: isize) -> Option<SimpleComputer> {
-        let mut instructions: Vec<Instruction> = vec![];
-        for line in raw_input.lines() {
-            let line = line.trim();
-            let instruction = {
-                if let Ok(Some(caps)) = REGEX_HLF.captures(line) {
-                    let register = caps[1].chars().next().unwrap();
-                    Instruction::Half { register }
-                } else if let Ok(Some(caps)) = REGEX_TPL.captures(line) {
-                    let register = caps[1].chars().next().unwrap();
-                    Instruction::Triple { register }
-                } else if let Ok(Some(caps)) = REGEX_INC.captures(line) {
-                    let register = caps[1].chars().next().unwrap();
-                    Instruction::Increment { register }
-                } else if let Ok(Some(caps)) = REGEX_JMP.captures(line) {
-                    let offset = caps[1].parse::<isize>().unwrap();
-                    Instruction::Jump { offset }
-                } else if let Ok(Some(caps)) = REGEX_JIE.captures(line) {
-                    let register = caps[1].chars().next().unwrap();
-                    let offset = caps[2].parse::<isize>().unwrap();
-                    Instruction::JumpIfEven { register, offset }
-                } else if let Ok(Some(caps)) = REGEX_JIO.captures(line) {
-                    let register = caps[1].chars().next().unwrap();
-                    let offset = caps[2].parse::<isize>().unwrap();
-                    Instruction::JumpIfOne { register, offset }
-                } else {
-                    // Invalid instruction, so the simple computer cannot be created
-                    return None;
-                }
-            };
-            instructions.push(instruction);
-        }
-        Some(SimpleComputer {
+    /// Creates a new simple computer by assembling the program, with the given registers all set
+    /// to 0. Uses of any other register name in the program are assembly errors.
+    pub fn new(raw_input: &str, register_names: &[&str]) -> Result<SimpleComputer, AssemblyError> {
+        let register_names: Vec<String> = register_names.iter().map(|s| s.to_string()).collect();
+        let instructions = assemble(raw_input, &register_names)?;
+        Ok(SimpleComputer {
             instructions,
             pc: 0,
-            register_a,
-            register_b,
+            registers: vec![0; register_names.len()],
+            register_names,
+            output: vec![],
+            steps: 0,
+            breakpoints: BTreeSet::new(),
+            watchpoints: BTreeSet::new(),
+            trace: None,
         })
     }
 
-    /// Gets the value of the "register_a" field.
-    pub fn register_a(&self) -> isize {
-        self.register_a
+    /// Disassembles the instructions stored in the simple computer into a program listing, giving
+    /// the address of each instruction and the absolute address targeted by each jump.
+    pub fn disassemble(&self) -> String {
+        disassemble(&self.instructions, &self.register_names)
+    }
+
+    /// Gets the value of the "pc" field.
+    pub fn pc(&self) -> isize {
+        self.pc
+    }
+
+    /// Updates the value of the "pc" field. Setting the program counter outside of the instruction
+    /// space halts the simple computer.
+    pub fn set_pc(&mut self, pc: isize) {
+        self.pc = pc;
     }
 
-    /// Gets the value of the "register_b" field.
-    pub fn register_b(&self) -> isize {
-        self.register_b
+    /// Gets the value of the "steps" field.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Gets the value of the "register_names" field.
+    pub fn register_names(&self) -> &[String] {
+        &self.register_names
+    }
+
+    /// Gets the value of the "registers" field, in the same order as the register names.
+    pub fn registers(&self) -> &[isize] {
+        &self.registers
+    }
+
+    /// Gets the value of the "output" field.
+    pub fn output(&self) -> &[isize] {
+        &self.output
+    }
+
+    /// Gets the value held in the named register, or None if the register does not exist.
+    pub fn register(&self, name: &str) -> Option<isize> {
+        Some(self.registers[self.register_index(name)?])
+    }
+
+    /// Updates the value held in the named register.
+    pub fn set_register(&mut self, name: &str, new_value: isize) -> Result<(), &'static str> {
+        match self.register_index(name) {
+            Some(register) => self.registers[register] = new_value,
+            None => return Err("Register does not exist!"),
+        }
+        Ok(())
+    }
+
+    /// Gets the index of the named register in the register file.
+    fn register_index(&self, name: &str) -> Option<usize> {
+        self.register_names.iter().position(|n| n == name)
+    }
+
+    /// Takes a snapshot of the machine state of the simple computer (program, program counter,
+    /// registers, output and step counter). Breakpoints, watchpoints and traces are not included.
+    pub fn snapshot(&self) -> MachineState {
+        MachineState {
+            program: self
+                .instructions
+                .iter()
+                .map(|instruction| instruction.format(&self.register_names))
+                .collect(),
+            pc: self.pc,
+            register_names: self.register_names.clone(),
+            registers: self.registers.clone(),
+            output: self.output.clone(),
+            steps: self.steps,
+        }
+    }
+
+    /// Restores the machine state of the simple computer from the snapshot. Breakpoints are kept,
+    /// watchpoints are kept for registers that still exist and a fresh trace is started if tracing
+    /// is enabled. The simple computer is unchanged if the snapshot is invalid.
+    pub fn restore(&mut self, snapshot: &MachineState) -> Result<(), &'static str> {
+        if snapshot.registers.len() != snapshot.register_names.len() {
+            return Err("Snapshot register values do not match register names!");
+        }
+        let instructions = match assemble(&snapshot.program.join("\n"), &snapshot.register_names) {
+            Ok(instructions) => instructions,
+            Err(_) => return Err("Snapshot program is not valid!"),
+        };
+        self.watchpoints = self
+            .watchpoints
+            .iter()
+            .filter_map(|&register| {
+                let name = &self.register_names[register];
+                snapshot.register_names.iter().position(|n| n == name)
+            })
+            .collect();
+        self.instructions = instructions;
+        self.pc = snapshot.pc;
+        self.register_names = snapshot.register_names.clone();
+        self.registers = snapshot.registers.clone();
+        self.output = snapshot.output.clone();
+        self.steps = snapshot.steps;
+        if self.trace.is_some() {
+            self.enable_trace();
+        }
+        Ok(())
+    }
+
+    /// Starts recording a trace of the instructions executed by the simple computer, discarding
+    /// any trace already recorded.
+    pub fn enable_trace(&mut self) {
+        let register_names: Vec<&str> = self.register_names.iter().map(|s| s.as_str()).collect();
+        self.trace = Some(ExecutionTrace::new(&register_names));
+    }
+
+    /// Stops recording the trace of executed instructions and returns the trace recorded, if
+    /// tracing was enabled.
+    pub fn take_trace(&mut self) -> Option<ExecutionTrace> {
+        self.trace.take()
+    }
+
+    /// Gets the trace of executed instructions recorded so far, if tracing is enabled.
+    pub fn trace(&self) -> Option<&ExecutionTrace> {
+        self.trace.as_ref()
+    }
+
+    /// Checks if the simple computer has halted (i.e., the program counter is outside of the
+    /// instruction space).
+    pub fn is_halted(&self) -> bool {
+        self.pc < 0 || self.pc as usize >= self.instructions.len()
+    }
+
+    /// Gets the instruction at the program counter in its input syntax, or None if the simple
+    /// computer has halted.
+    pub fn current_instruction(&self) -> Option<String> {
+        if self.is_halted() {
+            return None;
+        }
+        Some(self.instructions[self.pc as usize].format(&self.register_names))
     }
 
-    /// Updates the value of the "register_a" field.
-    pub fn set_register_a(&mut self, new_value: isize) {
-        self.register_a = new_value;
+    /// Adds a breakpoint, pausing execution before the instruction at the given address.
+    pub fn add_breakpoint(&mut self, pc: isize) {
+        self.breakpoints.insert(pc);
     }
 
-    /// Updates the value of the "register_b" field.
-    pub fn set_register_b(&mut self, new_value: isize) {
-        self.register_b = new_value;
+    /// Removes the breakpoint at the given address, if it exists.
+    pub fn remove_breakpoint(&mut self, pc: isize) {
+        self.breakpoints.remove(&pc);
+    }
+
+    /// Adds a watchpoint, pausing execution after an instruction changes the value of the given
+    /// register.
+    pub fn add_watchpoint(&mut self, name: &str) -> Result<(), &'static str> {
+        match self.register_index(name) {
+            Some(register) => _ = self.watchpoints.insert(register),
+            None => return Err("Register does not exist!"),
+        }
+        Ok(())
+    }
+
+    /// Removes the watchpoint on the given register, if it exists.
+    pub fn remove_watchpoint(&mut self, name: &str) {
+        if let Some(register) = self.register_index(name) {
+            self.watchpoints.remove(&register);
+        }
     }
 
     /// Executes the instructions stored in the simple computer and halts when the program counter
-    /// is outside of the instruction space.
+    /// is outside of the instruction space. Breakpoints and watchpoints are ignored.
     pub fn execute(&mut self) {
-        loop {
-            // Halt execution if outside of instruction space
-            if self.pc < 0 || self.pc as usize >= self.instructions.len() {
-                return;
-            }
-            // Execute the current instruction
-            let pc = self.pc as usize;
-            match self.instructions[pc] {
-                Instruction::Half { register } => {
-                    match register {
-                        'a' => self.register_a /= 2,
-                        'b' => self.register_b /= 2,
-                        _ => (),
+        while self.step() {}
+    }
+
+    /// Executes the instructions stored in the simple computer until it halts, replacing recognised
+    /// loop idioms with macro-instructions that have the same effect. Falls back to interpreting
+    /// the instructions one at a time where no idiom applies, or if tracing is enabled. The step
+    /// counter is advanced by the number of instructions that would have been interpreted, and the
+    /// number of instructions and macro-instructions actually dispatched is returned.
+    pub fn execute_optimised(&mut self) -> u64 {
+        let macro_instructions = find_macro_instructions(&self.instructions);
+        let mut dispatched = 0;
+        while !self.is_halted() {
+            dispatched += 1;
+            if self.trace.is_none() {
+                if let Some(macro_instruction) = &macro_instructions[self.pc as usize] {
+                    if let Some((offset, steps)) = macro_instruction.apply(&mut self.registers) {
+                        self.pc += offset;
+                        self.steps += steps;
+                        continue;
                     }
-                    self.pc += 1;
                 }
-                Instruction::Triple { register } => {
-                    match register {
-                        'a' => self.register_a *= 3,
-                        'b' => self.register_b *= 3,
-                        _ => (),
+            }
+            self.step();
+        }
+        dispatched
+    }
+
+    /// Executes the instructions stored in the simple computer until it halts, the given number of
+    /// instructions have been executed or it returns to a previous state (program counter and
+    /// register values), in which case it would otherwise loop forever.
+    pub fn execute_with_limit(&mut self, max_steps: u64) -> ExecutionOutcome {
+        let mut states_seen: HashMap<(isize, Vec<isize>), u64> = HashMap::new();
+        let mut steps_executed = 0;
+        loop {
+            if self.is_halted() {
+                return ExecutionOutcome::Halted {
+                    steps: self.steps,
+                    registers: self.registers.clone(),
+                };
+            }
+            if steps_executed >= max_steps {
+                return ExecutionOutcome::StepLimit {
+                    steps: self.steps,
+                    registers: self.registers.clone(),
+                };
+            }
+            // Check if the current state has been reached before
+            match states_seen.entry((self.pc, self.registers.clone())) {
+                Entry::Occupied(e) => {
+                    return ExecutionOutcome::LoopDetected {
+                        steps: self.steps,
+                        registers: self.registers.clone(),
+                        loop_start: *e.get(),
                     }
-                    self.pc += 1;
                 }
-                Instruction::Increment { register } => {
-                    match register {
-                        'a' => self.register_a += 1,
-                        'b' => self.register_b += 1,
-                        _ => (),
-                    }
+                Entry::Vacant(e) => _ = e.insert(self.steps),
+            }
+            self.step();
+            steps_executed += 1;
+        }
+    }
+
+    /// Executes instructions until the simple computer halts or reaches a breakpoint or watchpoint.
+    /// At least one instruction is executed (if not halted), so execution can be resumed from a
+    /// breakpoint.
+    pub fn run(&mut self) -> StopReason {
+        self.run_with_breakpoint(None)
+    }
+
+    /// Executes instructions until the program counter reaches the given breakpoint, or the simple
+    /// computer halts or reaches one of its own breakpoints or watchpoints.
+    pub fn run_until(&mut self, breakpoint: isize) -> StopReason {
+        self.run_with_breakpoint(Some(breakpoint))
+    }
+
+    /// Support function used to execute instructions until the simple computer halts, reaches the
+    /// given breakpoint or reaches one of its own breakpoints or watchpoints.
+    fn run_with_breakpoint(&mut self, breakpoint: Option<isize>) -> StopReason {
+        loop {
+            // Record watched register values before executing the next instruction
+            let old_values: Vec<(usize, isize)> = self
+                .watchpoints
+                .iter()
+                .map(|register| (*register, self.registers[*register]))
+                .collect();
+            if !self.step() {
+                return StopReason::Halted;
+            }
+            for (register, old_value) in old_values {
+                let new_value = self.registers[register];
+                if new_value != old_value {
+                    return StopReason::Watchpoint {
+                        register: self.register_names[register].to_string(),
+                        old_value,
+                        new_value,
+                    };
+                }
+            }
+            if self.is_halted() {
+                return StopReason::Halted;
+            }
+            if breakpoint == Some(self.pc) || self.breakpoints.contains(&self.pc) {
+                return StopReason::Breakpoint { pc: self.pc };
+            }
+        }
+    }
+
+    /// Executes the instruction at the program counter. Returns false if the simple computer has
+    /// halted, in which case no instruction is executed.
+    pub fn step(&mut self) -> bool {
+        // Halt execution if outside of instruction space
+        if self.is_halted() {
+            return false;
+        }
+        // Record register values before execution if tracing is enabled
+        let registers_before = self.trace.as_ref().map(|_| self.registers.clone());
+        // Execute the current instruction
+        self.steps += 1;
+        let pc = self.pc as usize;
+        match self.instructions[pc] {
+            Instruction::Half { register } => {
+                self.registers[register] /= 2;
+                self.pc += 1;
+            }
+            Instruction::Triple { register } => {
+                self.registers[register] *= 3;
+                self.pc += 1;
+            }
+            Instruction::Increment { register } => {
+                self.registers[register] += 1;
+                self.pc += 1;
+            }
+            Instruction::Decrement { register } => {
+                self.registers[register] -= 1;
+                self.pc += 1;
+            }
+            Instruction::Copy { source, register } => {
+                self.registers[register] = self.operand_value(source);
+                self.pc += 1;
+            }
+            Instruction::Jump { offset } => self.pc += offset,
+            Instruction::JumpIfEven { register, offset } => {
+                if self.registers[register] % 2 == 0 {
+                    self.pc += offset;
+                } else {
                     self.pc += 1;
                 }
-                Instruction::Jump { offset } => self.pc += offset,
-                Instruction::JumpIfEven { register, offset } => {
-                    let valid = match register {
-                        'a' => self.register_a % 2 == 0,
-                        'b' => self.register_b % 2 == 0,
-                        _ => false,
-                    };
-                    if valid {
-                        self.pc += offset;
-                    } else {
-                        self.pc += 1;
-                    }
+            }
+            Instruction::JumpIfOne { register, offset } => {
+                if self.registers[register] == 1 {
+                    self.pc += offset;
+                } else {
+                    self.pc += 1;
                 }
-                Instruction::JumpIfOne { register, offset } => {
-                    let valid = match register {
-                        'a' => self.register_a == 1,
-                        'b' => self.register_b == 1,
-                        _ => false,
-                    };
-                    if valid {
-                        self.pc += offset;
-                    } else {
-                        self.pc += 1;
-                    }
+            }
+            Instruction::JumpIfNotZero { condition, offset } => {
+                if self.operand_value(condition) != 0 {
+                    self.pc += offset;
+                } else {
+                    self.pc += 1;
                 }
             }
+            Instruction::Output { source } => {
+                self.output.push(self.operand_value(source));
+                self.pc += 1;
+            }
+        }
+        if let Some(registers_before) = registers_before {
+            let entry = TraceEntry::new(
+                self.steps,
+                pc as isize,
+                &self.instructions[pc].format(&self.register_names),
+                self.pc,
+                &registers_before,
+                &self.registers,
+            );
+            self.trace.as_mut().unwrap().push(entry);
+        }
+        true
+    }
+
+    /// Gets the value of the operand, being either the value held in a register or a specific
+    /// value.
+    fn operand_value(&self, operand: Operand) -> isize {
+        match operand {
+            Operand::Register(register) => self.registers[register],
+            Operand::Value(value) => value,
         }
     }
 }
@@ -1,80 +1,181 @@
-use strum::EnumIter;
-
-/// Represents the different spells that can be cast in the Wizard Simulator 20XX used in AOC 2015
-/// Day 22 (https://adventofcode.com/2015/day/22).
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, EnumIter)]
-pub enum Spell {
-    MagicMissile,
-    Drain,
-    Shield,
-    Poison,
-    Recharge,
+use serde::{Deserialize, Serialize};
+
+use super::MagicEntity;
+
+/// Represents the changes applied by a spell, either instantly when the spell is cast or on each
+/// turn that the spell effect is active. Damage is dealt to the target of the spell, while health
+/// and mana are restored to the caster.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpellEffect {
+    damage: i64,
+    heal: i64,
+    mana: i64,
 }
 
-impl Spell {
-    /// Gets the mana cost for the spell.
-    pub fn mana(&self) -> i64 {
-        match self {
-            Spell::MagicMissile => 53,
-            Spell::Drain => 73,
-            Spell::Shield => 113,
-            Spell::Poison => 173,
-            Spell::Recharge => 229,
-        }
+impl SpellEffect {
+    pub fn new(damage: i64, heal: i64, mana: i64) -> SpellEffect {
+        SpellEffect { damage, heal, mana }
     }
 
-    /// Gets the amount of damage dealt by the spell.
+    /// Gets the value of the "damage" field.
     pub fn damage(&self) -> i64 {
-        match self {
-            Spell::MagicMissile => 4,
-            Spell::Drain => 2,
-            Spell::Shield => 0,
-            Spell::Poison => 3,
-            Spell::Recharge => 0,
-        }
+        self.damage
+    }
+
+    /// Gets the value of the "heal" field.
+    pub fn heal(&self) -> i64 {
+        self.heal
+    }
+
+    /// Gets the value of the "mana" field.
+    pub fn mana(&self) -> i64 {
+        self.mana
     }
 
-    /// Gets the amount of health points healed by the spell.
-    pub fn health(&self) -> i64 {
-        match self {
-            Spell::MagicMissile => 0,
-            Spell::Drain => 2,
-            Spell::Shield => 0,
-            Spell::Poison => 0,
-            Spell::Recharge => 0,
+    /// Checks if the effect does not change anything.
+    pub fn is_empty(&self) -> bool {
+        *self == SpellEffect::default()
+    }
+}
+
+/// Represents a spell that can be cast in the Wizard Simulator 20XX used in AOC 2015 Day 22
+/// (https://adventofcode.com/2015/day/22). Spells are defined in a spellbook file rather than
+/// being hard-coded.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Spell {
+    #[serde(skip)]
+    id: usize, // position of the spell in its spellbook
+    name: String,
+    cost: i64,
+    #[serde(default)]
+    instant: SpellEffect,
+    #[serde(default)]
+    per_turn: SpellEffect,
+    #[serde(default)]
+    armour: i64, // armour given to the caster while the effect is active
+    #[serde(default)]
+    duration: i64, // number of turns that the effect lasts for
+}
+
+impl Spell {
+    pub fn new(
+        name: &str,
+        cost: i64,
+        instant: SpellEffect,
+        per_turn: SpellEffect,
+        armour: i64,
+        duration: i64,
+    ) -> Spell {
+        Spell {
+            id: 0,
+            name: name.to_string(),
+            cost,
+            instant,
+            per_turn,
+            armour,
+            duration,
         }
     }
 
-    /// Gets the amount of armour points given by the spell.
+    /// Gets the value of the "id" field.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Gets the value of the "name" field.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the value of the "cost" field.
+    pub fn cost(&self) -> i64 {
+        self.cost
+    }
+
+    /// Gets the value of the "instant" field.
+    pub fn instant(&self) -> SpellEffect {
+        self.instant
+    }
+
+    /// Gets the value of the "per_turn" field.
+    pub fn per_turn(&self) -> SpellEffect {
+        self.per_turn
+    }
+
+    /// Gets the value of the "armour" field.
     pub fn armour(&self) -> i64 {
-        match self {
-            Spell::MagicMissile => 0,
-            Spell::Drain => 0,
-            Spell::Shield => 7,
-            Spell::Poison => 0,
-            Spell::Recharge => 0,
-        }
+        self.armour
     }
 
-    /// Gets the amount of mana restored by the spell.
-    pub fn mana_heal(&self) -> i64 {
-        match self {
-            Spell::MagicMissile => 0,
-            Spell::Drain => 0,
-            Spell::Shield => 0,
-            Spell::Poison => 0,
-            Spell::Recharge => 101,
-        }
+    /// Gets the value of the "duration" field.
+    pub fn duration(&self) -> i64 {
+        self.duration
+    }
+
+    /// Checks if casting the spell starts an effect lasting over the following turns.
+    pub fn has_effect(&self) -> bool {
+        self.duration > 0
+    }
+
+    /// Checks if the spell deals damage to its target, either instantly or over the following
+    /// turns.
+    pub fn deals_damage(&self) -> bool {
+        self.instant.damage > 0 || (self.has_effect() && self.per_turn.damage > 0)
     }
+}
+
+/// Represents the collection of spells available to the caster in the Wizard Simulator 20XX.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Spellbook {
+    spells: Vec<Spell>,
+}
 
-    /// Gets the number of turns that the spell effect lasts for.
-    pub fn effect_turns(&self) -> i64 {
-        match self {
-            Spell::MagicMissile => 0,
-            Spell::Drain => 0,
-            Spell::Shield => 6,
-            Spell::Poison => 6,
-            Spell::Recharge => 5,
+impl Spellbook {
+    /// Creates a new spellbook containing the given spells. Returns an error if the spells are not
+    /// valid.
+    pub fn new(spells: &[Spell]) -> Result<Spellbook, String> {
+        let mut spells = spells.to_vec();
+        for (id, spell) in spells.iter_mut().enumerate() {
+            spell.id = id;
         }
+        for (i, spell) in spells.iter().enumerate() {
+            if spells[..i].iter().any(|other| other.name == spell.name) {
+                return Err(format!("Duplicate spell name: {}", spell.name));
+            }
+            if spell.cost < 0 || spell.duration < 0 {
+                return Err(format!("Negative cost or duration: {}", spell.name));
+            }
+            if !spell.has_effect() && (!spell.per_turn.is_empty() || spell.armour != 0) {
+                return Err(format!("Per-turn effect without duration: {}", spell.name));
+            }
+        }
+        let num_effects = spells.iter().filter(|spell| spell.has_effect()).count();
+        if num_effects > MagicEntity::MAX_ACTIVE_EFFECTS {
+            return Err(format!(
+                "Too many spells with lasting effects (maximum {})",
+                MagicEntity::MAX_ACTIVE_EFFECTS
+            ));
+        }
+        Ok(Spellbook { spells })
+    }
+
+    /// Parses the spellbook from a JSON array of spell specifications. Each spell has a "name" and
+    /// "cost", and optionally "instant" and "per_turn" effects (each with "damage", "heal" and
+    /// "mana" values), an "armour" bonus and the effect "duration" in turns.
+    pub fn from_json(raw_input: &str) -> Result<Spellbook, String> {
+        let spells: Vec<Spell> = serde_json::from_str(raw_input).map_err(|e| e.to_string())?;
+        Spellbook::new(&spells)
+    }
+
+    /// Gets the value of the "spells" field.
+    pub fn spells(&self) -> &[Spell] {
+        &self.spells
+    }
+
+    /// Gets the spell with the given name, or None if the spell is not in the spellbook.
+    pub fn get(&self, name: &str) -> Option<&Spell> {
+        self.spells.iter().find(|spell| spell.name == name)
     }
 }
@@ -0,0 +1,891 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde_json::{json, Value};
+
+use super::{EffectTick, Encounter, EncounterState, MagicEntity, Spell};
+
+/// Represents the rules modifying the difficulty of a fight in the Wizard Simulator 20XX. Rules
+/// are combined by starting from the easy difficulty and adding modifiers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Difficulty {
+    player_drain: i64,            // health lost at the start of each player turn
+    boss_damage_percent: i64,     // boss damage as a percentage of base damage
+    starting_effects: Vec<Spell>, // effects active on the player at the start
+    mana_cap: Option<i64>,        // maximum mana the player can hold
+}
+
+impl Difficulty {
+    /// Creates the easy difficulty, with no modifiers applied.
+    pub fn easy() -> Difficulty {
+        Difficulty {
+            player_drain: 0,
+            boss_damage_percent: 100,
+            starting_effects: vec![],
+            mana_cap: None,
+        }
+    }
+
+    /// Creates the HARD difficulty, where the player loses 1 health point at the start of each of
+    /// their turns.
+    pub fn hard() -> Difficulty {
+        Difficulty::easy().with_player_drain(1)
+    }
+
+    /// Sets the health lost by the player at the start of each of their turns (ignoring armour).
+    pub fn with_player_drain(mut self, player_drain: i64) -> Difficulty {
+        self.player_drain = player_drain;
+        self
+    }
+
+    /// Sets the damage dealt by the boss as a percentage of its base damage (rounded down), before
+    /// the armour of the player is applied.
+    pub fn with_boss_damage_percent(mut self, boss_damage_percent: i64) -> Difficulty {
+        self.boss_damage_percent = boss_damage_percent;
+        self
+    }
+
+    /// Adds a spell effect that is active on the player at the start of the fight.
+    pub fn with_starting_effect(mut self, spell: &Spell) -> Difficulty {
+        self.starting_effects.push(spell.clone());
+        self
+    }
+
+    /// Sets the maximum amount of mana that the player can hold.
+    pub fn with_mana_cap(mut self, mana_cap: i64) -> Difficulty {
+        self.mana_cap = Some(mana_cap);
+        self
+    }
+
+    /// Gets the value of the "player_drain" field.
+    pub fn player_drain(&self) -> i64 {
+        self.player_drain
+    }
+
+    /// Gets the value of the "boss_damage_percent" field.
+    pub fn boss_damage_percent(&self) -> i64 {
+        self.boss_damage_percent
+    }
+
+    /// Gets the value of the "starting_effects" field.
+    pub fn starting_effects(&self) -> &[Spell] {
+        &self.starting_effects
+    }
+
+    /// Gets the value of the "mana_cap" field.
+    pub fn mana_cap(&self) -> Option<i64> {
+        self.mana_cap
+    }
+
+    /// Prepares the player for the start of the fight by starting the starting effects and
+    /// applying the mana cap.
+    pub fn prepare_player(&self, player: &mut MagicEntity) {
+        for spell in self.starting_effects.iter() {
+            player.start_effect(spell);
+        }
+        self.limit_mana(player);
+    }
+
+    /// Calculates the damage dealt by the boss with the given base damage.
+    pub fn boss_damage(&self, base_damage: i64) -> i64 {
+        base_damage * self.boss_damage_percent / 100
+    }
+
+    /// Reduces the mana of the player to the mana cap, if there is one.
+    fn limit_mana(&self, player: &mut MagicEntity) {
+        if let Some(mana_cap) = self.mana_cap {
+            player.limit_mana(mana_cap);
+        }
+    }
+}
+
+/// Represents the two sides of a fight in the Wizard Simulator 20XX. All enemies are on the side
+/// of the boss.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Combatant {
+    Player,
+    Boss,
+}
+
+impl fmt::Display for Combatant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Combatant::Player => write!(f, "player"),
+            Combatant::Boss => write!(f, "boss"),
+        }
+    }
+}
+
+/// Represents the outcome of a round of the fight (player turn followed by boss turn).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoundOutcome {
+    Ongoing,
+    PlayerWon { spell_cast: bool }, // boss can die from effects before the spell is cast
+    PlayerLost,
+    InvalidSpell,
+}
+
+/// Represents the events that occur during a fight in the Wizard Simulator 20XX. Player spells are
+/// given by their id in the spellbook, while enemies (and spell targets) are given by their index
+/// in the encounter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FightEvent {
+    TurnStart {
+        attacker: Combatant,
+        player_health: i64,
+        player_armour: i64,
+        player_mana: i64,
+    },
+    EnemyStatus {
+        enemy: usize,
+        health: i64,
+        armour: i64,
+        mana: i64,
+    },
+    EffectStarted {
+        spell_id: usize,
+    },
+    DifficultyDamage {
+        damage: i64,
+    },
+    EffectTick(EffectTick),
+    EnemyEffectTick {
+        enemy: usize,
+        tick: EffectTick,
+    },
+    SpellCast {
+        spell_id: usize,
+        target: usize,
+        damage: i64,
+        heal: i64,
+        mana: i64,
+        armour: i64,
+    },
+    CannotCast {
+        spell_id: usize,
+    },
+    EnemyCast {
+        enemy: usize,
+        spell_id: usize,
+        damage: i64,
+        heal: i64,
+        mana: i64,
+        armour: i64,
+    },
+    EnemyAttack {
+        enemy: usize,
+        damage: i64,
+        armour: i64,
+        dealt: i64,
+    },
+    EnemyDefeated {
+        enemy: usize,
+    },
+    Victory {
+        winner: Combatant,
+    },
+}
+
+/// Represents the log of events from a simulated fight in the Wizard Simulator 20XX, along with
+/// the winner of the fight (None if the spells ran out before the fight was decided).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BattleLog {
+    spell_names: HashMap<usize, String>,
+    enemy_names: Vec<String>,
+    enemy_spell_names: HashMap<(usize, usize), String>, // keyed by enemy index and spell id
+    events: Vec<FightEvent>,
+    winner: Option<Combatant>,
+}
+
+impl BattleLog {
+    /// Gets the value of the "events" field.
+    pub fn events(&self) -> &[FightEvent] {
+        &self.events
+    }
+
+    /// Gets the value of the "winner" field.
+    pub fn winner(&self) -> Option<Combatant> {
+        self.winner
+    }
+
+    /// Converts the log into the narrative style used in the problem description, with a blank
+    /// line between turns.
+    pub fn to_narrative(&self) -> String {
+        let mut output = String::new();
+        for event in self.events.iter() {
+            if matches!(event, FightEvent::TurnStart { .. }) && !output.is_empty() {
+                output.push('\n');
+            }
+            output.push_str(&self.narrate(event));
+            output.push('\n');
+        }
+        output
+    }
+
+    /// Converts the log into a JSON object containing the winner and the list of events.
+    pub fn to_json(&self) -> String {
+        let events: Vec<Value> = self.events.iter().map(|e| self.event_json(e)).collect();
+        let winner = self.winner.map(|winner| winner.to_string());
+        serde_json::to_string_pretty(&json!({ "winner": winner, "events": events })).unwrap()
+    }
+
+    /// Checks if the fight was against more than one enemy.
+    fn has_multiple_enemies(&self) -> bool {
+        self.enemy_names.len() > 1
+    }
+
+    /// Gets the name of the player spell with the given id.
+    fn spell_name(&self, spell_id: usize) -> String {
+        match self.spell_names.get(&spell_id) {
+            Some(name) => name.to_string(),
+            None => format!("Spell #{}", spell_id),
+        }
+    }
+
+    /// Gets the name of the enemy with the given index.
+    fn enemy_name(&self, enemy: usize) -> String {
+        match self.enemy_names.get(enemy) {
+            Some(name) => name.to_string(),
+            None => format!("Enemy #{}", enemy),
+        }
+    }
+
+    /// Gets the name of the spell with the given id cast by the enemy with the given index.
+    fn enemy_spell_name(&self, enemy: usize, spell_id: usize) -> String {
+        match self.enemy_spell_names.get(&(enemy, spell_id)) {
+            Some(name) => name.to_string(),
+            None => format!("Spell #{}", spell_id),
+        }
+    }
+
+    /// Describes the event in the narrative style used in the problem description.
+    fn narrate(&self, event: &FightEvent) -> String {
+        match *event {
+            FightEvent::TurnStart {
+                attacker,
+                player_health,
+                player_armour,
+                player_mana,
+            } => {
+                let title = match attacker {
+                    Combatant::Player => "Player",
+                    Combatant::Boss if self.has_multiple_enemies() => "Enemy",
+                    Combatant::Boss => "Boss",
+                };
+                format!(
+                    "-- {} turn --\n- Player has {} hit points, {} armor, {} mana",
+                    title, player_health, player_armour, player_mana
+                )
+            }
+            FightEvent::EnemyStatus {
+                enemy,
+                health,
+                armour,
+                mana,
+            } => match (armour, mana) {
+                (0, 0) => format!("- {} has {} hit points", self.enemy_name(enemy), health),
+                _ => format!(
+                    "- {} has {} hit points, {} armor, {} mana",
+                    self.enemy_name(enemy),
+                    health,
+                    armour,
+                    mana
+                ),
+            },
+            FightEvent::EffectStarted { spell_id } => {
+                format!("Player starts with {} active.", self.spell_name(spell_id))
+            }
+            FightEvent::DifficultyDamage { damage } => {
+                format!("Player loses {} hit points from the difficulty.", damage)
+            }
+            FightEvent::EffectTick(tick) => {
+                let target = match self.has_multiple_enemies() {
+                    true => Some(self.enemy_name(tick.target())),
+                    false => None,
+                };
+                narrate_tick(&self.spell_name(tick.spell_id()), &tick, target)
+            }
+            FightEvent::EnemyEffectTick { enemy, tick } => {
+                let name = format!(
+                    "{}'s {}",
+                    self.enemy_name(enemy),
+                    self.enemy_spell_name(enemy, tick.spell_id())
+                );
+                narrate_tick(&name, &tick, None)
+            }
+            FightEvent::SpellCast {
+                spell_id,
+                target,
+                damage,
+                heal,
+                mana,
+                armour,
+            } => {
+                let mut caster = format!("Player casts {}", self.spell_name(spell_id));
+                if self.has_multiple_enemies() {
+                    caster.push_str(&format!(" at {}", self.enemy_name(target)));
+                }
+                narrate_cast(&caster, damage, heal, mana, armour)
+            }
+            FightEvent::CannotCast { spell_id } => {
+                format!("Player cannot cast {}.", self.spell_name(spell_id))
+            }
+            FightEvent::EnemyCast {
+                enemy,
+                spell_id,
+                damage,
+                heal,
+                mana,
+                armour,
+            } => {
+                let caster = format!(
+                    "{} casts {}",
+                    self.enemy_name(enemy),
+                    self.enemy_spell_name(enemy, spell_id)
+                );
+                narrate_cast(&caster, damage, heal, mana, armour)
+            }
+            FightEvent::EnemyAttack {
+                enemy,
+                damage,
+                armour,
+                dealt,
+            } => match armour {
+                0 => format!("{} attacks for {} damage.", self.enemy_name(enemy), dealt),
+                _ => format!(
+                    "{} attacks for {} - {} = {} damage!",
+                    self.enemy_name(enemy),
+                    damage,
+                    armour,
+                    dealt
+                ),
+            },
+            FightEvent::EnemyDefeated { enemy } => {
+                format!("{} is defeated.", self.enemy_name(enemy))
+            }
+            FightEvent::Victory { winner } => match (winner, self.has_multiple_enemies()) {
+                (Combatant::Player, false) => {
+                    String::from("This kills the boss, and the player wins.")
+                }
+                (Combatant::Player, true) => {
+                    String::from("All enemies are defeated, and the player wins.")
+                }
+                (Combatant::Boss, false) => {
+                    String::from("This kills the player, and the boss wins.")
+                }
+                (Combatant::Boss, true) => {
+                    String::from("This kills the player, and the enemies win.")
+                }
+            },
+        }
+    }
+
+    /// Converts the event into a JSON object.
+    fn event_json(&self, event: &FightEvent) -> Value {
+        match *event {
+            FightEvent::TurnStart {
+                attacker,
+                player_health,
+                player_armour,
+                player_mana,
+            } => json!({
+                "event": "turn_start",
+                "attacker": attacker.to_string(),
+                "player_health": player_health,
+                "player_armour": player_armour,
+                "player_mana": player_mana,
+            }),
+            FightEvent::EnemyStatus {
+                enemy,
+                health,
+                armour,
+                mana,
+            } => json!({
+                "event": "enemy_status",
+                "enemy": self.enemy_name(enemy),
+                "health": health,
+                "armour": armour,
+                "mana": mana,
+            }),
+            FightEvent::EffectStarted { spell_id } => json!({
+                "event": "effect_started",
+                "spell": self.spell_name(spell_id),
+            }),
+            FightEvent::DifficultyDamage { damage } => json!({
+                "event": "difficulty_damage",
+                "damage": damage,
+            }),
+            FightEvent::EffectTick(tick) => {
+                let mut value = json!({
+                    "event": "effect_tick",
+                    "spell": self.spell_name(tick.spell_id()),
+                    "damage": tick.effect().damage(),
+                    "heal": tick.effect().heal(),
+                    "mana": tick.effect().mana(),
+                    "timer": tick.timer(),
+                    "armour_removed": tick.armour_removed(),
+                });
+                // Only effects dealing damage are aimed at an enemy
+                if tick.effect().damage() > 0 {
+                    value["target"] = json!(self.enemy_name(tick.target()));
+                }
+                value
+            }
+            FightEvent::EnemyEffectTick { enemy, tick } => json!({
+                "event": "enemy_effect_tick",
+                "enemy": self.enemy_name(enemy),
+                "spell": self.enemy_spell_name(enemy, tick.spell_id()),
+                "damage": tick.effect().damage(),
+                "heal": tick.effect().heal(),
+                "mana": tick.effect().mana(),
+                "timer": tick.timer(),
+                "armour_removed": tick.armour_removed(),
+            }),
+            FightEvent::SpellCast {
+                spell_id,
+                target,
+                damage,
+                heal,
+                mana,
+                armour,
+            } => json!({
+                "event": "spell_cast",
+                "spell": self.spell_name(spell_id),
+                "target": self.enemy_name(target),
+                "damage": damage,
+                "heal": heal,
+                "mana": mana,
+                "armour": armour,
+            }),
+            FightEvent::CannotCast { spell_id } => json!({
+                "event": "cannot_cast",
+                "spell": self.spell_name(spell_id),
+            }),
+            FightEvent::EnemyCast {
+                enemy,
+                spell_id,
+                damage,
+                heal,
+                mana,
+                armour,
+            } => json!({
+                "event": "enemy_cast",
+                "enemy": self.enemy_name(enemy),
+                "spell": self.enemy_spell_name(enemy, spell_id),
+                "damage": damage,
+                "heal": heal,
+                "mana": mana,
+                "armour": armour,
+            }),
+            FightEvent::EnemyAttack {
+                enemy,
+                damage,
+                armour,
+                dealt,
+            } => json!({
+                "event": "enemy_attack",
+                "enemy": self.enemy_name(enemy),
+                "damage": damage,
+                "armour": armour,
+                "dealt": dealt,
+            }),
+            FightEvent::EnemyDefeated { enemy } => json!({
+                "event": "enemy_defeated",
+                "enemy": self.enemy_name(enemy),
+            }),
+            FightEvent::Victory { winner } => json!({
+                "event": "victory",
+                "winner": winner.to_string(),
+            }),
+        }
+    }
+}
+
+/// Simulates the fight between the player and the boss under the rules of the difficulty, with
+/// the player casting the given spells in order. Returns the log of events from the fight. The
+/// fight ends early if either side is defeated, and the player loses if they cannot cast the next
+/// spell.
+pub fn simulate_fight(
+    player: &MagicEntity,
+    boss: &MagicEntity,
+    spells: &[Spell],
+    difficulty: &Difficulty,
+) -> BattleLog {
+    let casts: Vec<(Spell, usize)> = spells.iter().map(|spell| (spell.clone(), 0)).collect();
+    simulate_encounter(player, &Encounter::single(boss), &casts, difficulty)
+}
+
+/// Simulates the encounter between the player and the enemies under the rules of the difficulty,
+/// with the player casting the given spells at the enemies with the given indices in order.
+/// Returns the log of events from the fight. If the spells run out, the start of the next player
+/// turn is included when it decides the fight (e.g., the enemies being killed by an effect).
+pub fn simulate_encounter(
+    player: &MagicEntity,
+    encounter: &Encounter,
+    casts: &[(Spell, usize)],
+    difficulty: &Difficulty,
+) -> BattleLog {
+    let mut state = encounter.start(player, difficulty);
+    let mut events: Vec<FightEvent> = difficulty
+        .starting_effects()
+        .iter()
+        .map(|spell| FightEvent::EffectStarted {
+            spell_id: spell.id(),
+        })
+        .collect();
+    let mut winner: Option<Combatant> = None;
+    for (spell, target) in casts {
+        let outcome = play_round(
+            encounter,
+            &mut state,
+            spell,
+            *target,
+            difficulty,
+            Some(&mut events),
+        );
+        winner = match outcome {
+            RoundOutcome::Ongoing => continue,
+            RoundOutcome::PlayerWon { .. } => Some(Combatant::Player),
+            RoundOutcome::PlayerLost | RoundOutcome::InvalidSpell => Some(Combatant::Boss),
+        };
+        break;
+    }
+    if winner.is_none() {
+        let mut reported = unreported_defeats(encounter, &state);
+        let mut turn_events: Vec<FightEvent> = vec![];
+        let mut record = |event: FightEvent| turn_events.push(event);
+        winner = match start_player_turn(&mut state, difficulty, &mut reported, &mut record) {
+            Some(RoundOutcome::PlayerWon { .. }) => Some(Combatant::Player),
+            Some(_) => Some(Combatant::Boss),
+            None => None,
+        };
+        if winner.is_some() {
+            events.extend(turn_events);
+        }
+    }
+    let enemies = encounter.enemies();
+    BattleLog {
+        spell_names: casts
+            .iter()
+            .map(|(spell, _)| spell)
+            .chain(difficulty.starting_effects().iter())
+            .map(|spell| (spell.id(), spell.name().to_string()))
+            .collect(),
+        enemy_names: enemies
+            .iter()
+            .map(|enemy| enemy.name().to_string())
+            .collect(),
+        enemy_spell_names: enemies
+            .iter()
+            .enumerate()
+            .flat_map(|(i, enemy)| {
+                enemy
+                    .spells()
+                    .into_iter()
+                    .map(move |spell| ((i, spell.id()), spell.name().to_string()))
+            })
+            .collect(),
+        events,
+        winner,
+    }
+}
+
+/// Plays a round of the encounter under the rules of the difficulty, with the player casting the
+/// given spell at the enemy with the given index in their turn, followed by each enemy that is
+/// still alive acting in its turn. The state should have been created by the encounter. Events
+/// from the round are added to the log, if given.
+pub fn play_round(
+    encounter: &Encounter,
+    state: &mut EncounterState,
+    spell: &Spell,
+    target: usize,
+    difficulty: &Difficulty,
+    mut log: Option<&mut Vec<FightEvent>>,
+) -> RoundOutcome {
+    let mut record = |event: FightEvent| {
+        if let Some(events) = log.as_mut() {
+            events.push(event);
+        }
+    };
+    let mut reported = unreported_defeats(encounter, state);
+    // Player turn
+    if let Some(outcome) = start_player_turn(state, difficulty, &mut reported, &mut record) {
+        return outcome;
+    }
+    // // Cast the spell at the target then check if the enemies are defeated
+    if !state.is_enemy_alive(target) {
+        return cannot_cast(spell, &mut record);
+    }
+    let (target_health, player_health) = (state.enemies[target].health(), state.player.health());
+    let (player_mana, player_armour) = (state.player.mana(), state.player.armour());
+    if state
+        .player
+        .cast_spell_at(spell, &mut state.enemies, target, false)
+        .is_err()
+    {
+        return cannot_cast(spell, &mut record);
+    }
+    record(FightEvent::SpellCast {
+        spell_id: spell.id(),
+        target,
+        damage: target_health - state.enemies[target].health(),
+        heal: state.player.health() - player_health,
+        mana: state.player.mana() + spell.cost() - player_mana,
+        armour: state.player.armour() - player_armour,
+    });
+    difficulty.limit_mana(&mut state.player);
+    record_defeats(state, &mut reported, &mut record);
+    if state.enemies_defeated() {
+        record(FightEvent::Victory {
+            winner: Combatant::Player,
+        });
+        return RoundOutcome::PlayerWon { spell_cast: true };
+    }
+    // Enemy turn
+    record_turn_start(Combatant::Boss, state, &mut record);
+    // // Process effects and check if either side is defeated
+    match process_effects(state, difficulty, &mut reported, &mut record) {
+        Some(Combatant::Player) => return RoundOutcome::PlayerWon { spell_cast: true },
+        Some(Combatant::Boss) => return RoundOutcome::PlayerLost,
+        None => (),
+    }
+    // // Each enemy still alive acts in turn, then check if player is dead
+    for (i, enemy) in encounter.enemies().iter().enumerate() {
+        if state.enemies[i].is_dead() {
+            continue;
+        }
+        let position = state.positions[i];
+        state.positions[i] = enemy.next_position(position);
+        let player_health = state.player.health();
+        match enemy.choose_spell(&state.enemies[i], &state.player, position) {
+            Some(enemy_spell) => {
+                let entity = &mut state.enemies[i];
+                let (enemy_health, enemy_mana, enemy_armour) =
+                    (entity.health(), entity.mana(), entity.armour());
+                if entity
+                    .cast_spell(enemy_spell, &mut state.player, false)
+                    .is_ok()
+                {
+                    record(FightEvent::EnemyCast {
+                        enemy: i,
+                        spell_id: enemy_spell.id(),
+                        damage: player_health - state.player.health(),
+                        heal: entity.health() - enemy_health,
+                        mana: entity.mana() + enemy_spell.cost() - enemy_mana,
+                        armour: entity.armour() - enemy_armour,
+                    });
+                }
+            }
+            None => {
+                let enemy_damage = difficulty.boss_damage(state.enemies[i].damage());
+                state.player.deal_damage(enemy_damage, false);
+                record(FightEvent::EnemyAttack {
+                    enemy: i,
+                    damage: enemy_damage,
+                    armour: state.player.armour(),
+                    dealt: player_health - state.player.health(),
+                });
+            }
+        }
+        if state.player.is_dead() {
+            record(FightEvent::Victory {
+                winner: Combatant::Boss,
+            });
+            return RoundOutcome::PlayerLost;
+        }
+    }
+    RoundOutcome::Ongoing
+}
+
+/// Starts the player turn by applying the difficulty drain and processing effects. Returns the
+/// outcome of the round if either side is defeated before the player casts a spell.
+fn start_player_turn(
+    state: &mut EncounterState,
+    difficulty: &Difficulty,
+    reported: &mut [bool],
+    record: &mut impl FnMut(FightEvent),
+) -> Option<RoundOutcome> {
+    record_turn_start(Combatant::Player, state, record);
+    // Apply difficulty drain and check if player is dead
+    if difficulty.player_drain() > 0 {
+        state.player.deal_damage(difficulty.player_drain(), true);
+        record(FightEvent::DifficultyDamage {
+            damage: difficulty.player_drain(),
+        });
+    }
+    if state.player.is_dead() {
+        record(FightEvent::Victory {
+            winner: Combatant::Boss,
+        });
+        return Some(RoundOutcome::PlayerLost);
+    }
+    // Process effects and check if either side is defeated
+    match process_effects(state, difficulty, reported, record) {
+        Some(Combatant::Player) => Some(RoundOutcome::PlayerWon { spell_cast: false }),
+        Some(Combatant::Boss) => Some(RoundOutcome::PlayerLost),
+        None => None,
+    }
+}
+
+/// Gets the list of enemies that have already been defeated, used to report each defeat once.
+/// Defeats are only reported if there is more than one enemy, so the list is otherwise empty.
+fn unreported_defeats(encounter: &Encounter, state: &EncounterState) -> Vec<bool> {
+    match encounter.enemies().len() > 1 {
+        true => state.enemies.iter().map(|enemy| enemy.is_dead()).collect(),
+        false => vec![],
+    }
+}
+
+/// Processes the effects active on the player (applied to their targets) followed by the effects
+/// active on the enemies still alive (applied to the player). Returns the winner if either side is
+/// defeated by the effects.
+fn process_effects(
+    state: &mut EncounterState,
+    difficulty: &Difficulty,
+    reported: &mut [bool],
+    record: &mut impl FnMut(FightEvent),
+) -> Option<Combatant> {
+    for tick in state.player.process_effects_on(&mut state.enemies) {
+        record(FightEvent::EffectTick(tick));
+    }
+    difficulty.limit_mana(&mut state.player);
+    record_defeats(state, reported, record);
+    if state.enemies_defeated() {
+        record(FightEvent::Victory {
+            winner: Combatant::Player,
+        });
+        return Some(Combatant::Player);
+    }
+    for (i, enemy) in state.enemies.iter_mut().enumerate() {
+        if enemy.is_dead() {
+            continue;
+        }
+        for tick in enemy.process_effects(&mut state.player) {
+            record(FightEvent::EnemyEffectTick { enemy: i, tick });
+        }
+    }
+    if state.player.is_dead() {
+        record(FightEvent::Victory {
+            winner: Combatant::Boss,
+        });
+        return Some(Combatant::Boss);
+    }
+    None
+}
+
+/// Records the events for the player being unable to cast the spell, which loses the fight.
+fn cannot_cast(spell: &Spell, record: &mut impl FnMut(FightEvent)) -> RoundOutcome {
+    record(FightEvent::CannotCast {
+        spell_id: spell.id(),
+    });
+    record(FightEvent::Victory {
+        winner: Combatant::Boss,
+    });
+    RoundOutcome::InvalidSpell
+}
+
+/// Records the events for the start of the attacker's turn, giving the current state of the
+/// player and each of the enemies.
+fn record_turn_start(
+    attacker: Combatant,
+    state: &EncounterState,
+    record: &mut impl FnMut(FightEvent),
+) {
+    record(FightEvent::TurnStart {
+        attacker,
+        player_health: state.player.health(),
+        player_armour: state.player.armour(),
+        player_mana: state.player.mana(),
+    });
+    for (i, enemy) in state.enemies.iter().enumerate() {
+        record(FightEvent::EnemyStatus {
+            enemy: i,
+            health: enemy.health(),
+            armour: enemy.armour(),
+            mana: enemy.mana(),
+        });
+    }
+}
+
+/// Records an event for each enemy defeated since it was last checked. Only enemies included in
+/// the reported list are checked.
+fn record_defeats(
+    state: &EncounterState,
+    reported: &mut [bool],
+    record: &mut impl FnMut(FightEvent),
+) {
+    for (i, (reported, enemy)) in reported.iter_mut().zip(state.enemies.iter()).enumerate() {
+        if !*reported && enemy.is_dead() {
+            *reported = true;
+            record(FightEvent::EnemyDefeated { enemy: i });
+        }
+    }
+}
+
+/// Describes the tick of an effect in the narrative style used in the problem description, naming
+/// the target of any damage dealt if given.
+fn narrate_tick(name: &str, tick: &EffectTick, target: Option<String>) -> String {
+    let effect = tick.effect();
+    let mut parts: Vec<String> = vec![];
+    if effect.damage() > 0 {
+        match target {
+            Some(target) => parts.push(format!("deals {} damage to {}", effect.damage(), target)),
+            None => parts.push(format!("deals {} damage", effect.damage())),
+        }
+    }
+    if effect.heal() > 0 {
+        parts.push(format!("heals {} hit points", effect.heal()));
+    }
+    if effect.mana() > 0 {
+        parts.push(format!("provides {} mana", effect.mana()));
+    }
+    let mut line = match parts.is_empty() {
+        true => format!("{}'s timer is now {}.", name, tick.timer()),
+        false => format!(
+            "{} {}; its timer is now {}.",
+            name,
+            join_clauses(&parts),
+            tick.timer()
+        ),
+    };
+    if tick.has_worn_off() {
+        match tick.armour_removed() {
+            0 => line.push_str(&format!("\n{} wears off.", name)),
+            armour => line.push_str(&format!(
+                "\n{} wears off, decreasing armor by {}.",
+                name, armour
+            )),
+        }
+    }
+    line
+}
+
+/// Describes a spell being cast in the narrative style used in the problem description.
+fn narrate_cast(caster: &str, damage: i64, heal: i64, mana: i64, armour: i64) -> String {
+    let mut parts: Vec<String> = vec![];
+    if damage > 0 {
+        parts.push(format!("dealing {} damage", damage));
+    }
+    if heal > 0 {
+        parts.push(format!("healing {} hit points", heal));
+    }
+    if mana > 0 {
+        parts.push(format!("restoring {} mana", mana));
+    }
+    if armour > 0 {
+        parts.push(format!("increasing armor by {}", armour));
+    }
+    match parts.is_empty() {
+        true => format!("{}.", caster),
+        false => format!("{}, {}.", caster, join_clauses(&parts)),
+    }
+}
+
+/// Joins the clauses of a sentence with commas, placing "and" before the last clause if there is
+/// more than one clause.
+fn join_clauses(clauses: &[String]) -> String {
+    match clauses.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{}, and {}", rest.join(", "), last),
+        _ => clauses.join(", "),
+    }
+}